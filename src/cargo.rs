@@ -0,0 +1,344 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{comments::LineStats, lang::Language, report::FileReport};
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Workspace {
+    members: Vec<String>,
+    exclude: Vec<String>,
+}
+
+pub struct Crate {
+    pub name: String,
+    pub root: PathBuf,
+}
+
+/// Part of a crate that files are attributed to, based on Cargo's default target layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CrateTarget {
+    Source,
+    Build,
+    Benches,
+    Examples,
+    Tests,
+}
+
+impl CrateTarget {
+    pub const ALL: &[CrateTarget] = &[
+        CrateTarget::Source,
+        CrateTarget::Build,
+        CrateTarget::Benches,
+        CrateTarget::Examples,
+        CrateTarget::Tests,
+    ];
+
+    /// `path` is relative to the crate root
+    fn of(path: &Path) -> CrateTarget {
+        let mut components = path.components();
+        let first = components.next().map(|c| c.as_os_str());
+        let is_file = components.next().is_none();
+
+        match first.and_then(|first| first.to_str()) {
+            Some("build.rs") if is_file => CrateTarget::Build,
+            Some("benches") if !is_file => CrateTarget::Benches,
+            Some("examples") if !is_file => CrateTarget::Examples,
+            Some("tests") if !is_file => CrateTarget::Tests,
+            _ => CrateTarget::Source,
+        }
+    }
+}
+
+impl Display for CrateTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CrateTarget::Source => "source",
+            CrateTarget::Build => "build",
+            CrateTarget::Benches => "benches",
+            CrateTarget::Examples => "examples",
+            CrateTarget::Tests => "tests",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct CrateReport {
+    pub name: String,
+    pub root: PathBuf,
+    pub targets: BTreeMap<CrateTarget, HashMap<Language, LineStats>>,
+}
+
+impl CrateReport {
+    pub fn code(&self) -> usize {
+        self.targets
+            .values()
+            .flat_map(|languages| languages.values())
+            .map(|stats| stats.code)
+            .sum()
+    }
+
+    pub fn target_code(&self, target: CrateTarget) -> usize {
+        self.targets.get(&target).map_or(0, |languages| {
+            languages.values().map(|stats| stats.code).sum()
+        })
+    }
+
+    /// Languages of the whole crate, sorted from most to least code
+    pub fn languages(&self) -> Vec<(Language, LineStats)> {
+        let mut languages: HashMap<Language, LineStats> = HashMap::new();
+        for (lang, stats) in self.targets.values().flatten() {
+            *languages.entry(*lang).or_default() += *stats;
+        }
+
+        sorted(languages)
+    }
+
+    /// Languages of a single target, sorted from most to least code
+    pub fn target_languages(&self, target: CrateTarget) -> Vec<(Language, LineStats)> {
+        self.targets
+            .get(&target)
+            .cloned()
+            .map_or(Vec::new(), sorted)
+    }
+}
+
+fn sorted(languages: HashMap<Language, LineStats>) -> Vec<(Language, LineStats)> {
+    languages
+        .into_iter()
+        .sorted_by(|a, b| Ord::cmp(&b.1.code, &a.1.code))
+        .collect()
+}
+
+/// Lists the crates of the workspace at `root`, or the single crate if it isn't a workspace
+pub fn workspace_crates(root: &Path) -> Result<Vec<Crate>, String> {
+    let manifest = read_manifest(&root.join("Cargo.toml"))?;
+
+    let mut crates = Vec::new();
+    if let Some(package) = manifest.package {
+        crates.push(Crate {
+            name: package.name,
+            root: root.to_path_buf(),
+        });
+    }
+
+    let workspace = manifest.workspace.unwrap_or_default();
+    let excluded = workspace
+        .exclude
+        .iter()
+        .map(|exclude| root.join(exclude))
+        .collect::<Vec<_>>();
+    for member in workspace.members.iter() {
+        for path in expand_member(root, member) {
+            if excluded.contains(&path) || crates.iter().any(|c| c.root == path) {
+                continue;
+            }
+            let manifest_path = path.join("Cargo.toml");
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let name = match read_manifest(&manifest_path)?.package {
+                Some(package) => package.name,
+                None => path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            crates.push(Crate { name, root: path });
+        }
+    }
+
+    Ok(crates)
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+
+    toml::from_str(&contents).map_err(|e| format!("{}: {}", path.to_string_lossy(), e.message()))
+}
+
+/// Expands the `*` and `?` wildcards in a workspace member path
+fn expand_member(root: &Path, member: &str) -> Vec<PathBuf> {
+    let mut paths = vec![root.to_path_buf()];
+    for component in member.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !component.contains(['*', '?']) {
+            paths = paths.into_iter().map(|path| path.join(component)).collect();
+            continue;
+        }
+
+        paths = paths
+            .into_iter()
+            .flat_map(|path| std::fs::read_dir(path).into_iter().flatten().flatten())
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .filter(|entry| wildcard_match(component, &entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .sorted()
+            .collect();
+    }
+
+    paths
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.chars().next() {
+        None => name.is_empty(),
+        Some('*') => {
+            let rest = &pattern[1..];
+            name.char_indices()
+                .map(|(i, _)| i)
+                .chain([name.len()])
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+        Some(c) => {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(n) if c == '?' || c == n => {
+                    wildcard_match(&pattern[c.len_utf8()..], chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Adds up the files of each crate by target, a file belongs to the innermost crate that contains
+/// it. Files outside of every crate are put under a "(workspace)" entry at `root`. Sorted from
+/// most to least code.
+pub fn group_by_crate(files: &[FileReport], crates: &[Crate], root: &Path) -> Vec<CrateReport> {
+    let mut reports = crates
+        .iter()
+        .map(|c| CrateReport {
+            name: c.name.clone(),
+            root: c.root.clone(),
+            targets: BTreeMap::new(),
+        })
+        .collect::<Vec<_>>();
+    let mut workspace = CrateReport {
+        name: String::from("(workspace)"),
+        root: root.to_path_buf(),
+        targets: BTreeMap::new(),
+    };
+
+    for file in files {
+        let report = reports
+            .iter_mut()
+            .filter(|report| file.path.starts_with(&report.root))
+            .max_by_key(|report| report.root.components().count());
+        let (report, target) = match report {
+            Some(report) => {
+                let target = CrateTarget::of(file.path.strip_prefix(&report.root).unwrap());
+                (report, target)
+            }
+            None => (&mut workspace, CrateTarget::Source),
+        };
+
+        *report
+            .targets
+            .entry(target)
+            .or_default()
+            .entry(file.lang)
+            .or_default() += file.stats;
+    }
+
+    if !workspace.targets.is_empty() {
+        reports.push(workspace);
+    }
+    reports.sort_by(|a, b| b.code().cmp(&a.code()).then(a.name.cmp(&b.name)));
+
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("src/lib.rs", CrateTarget::Source)]
+    #[test_case("build.rs", CrateTarget::Build)]
+    #[test_case("src/build.rs", CrateTarget::Source)]
+    #[test_case("benches/parse.rs", CrateTarget::Benches)]
+    #[test_case("examples/demo/main.rs", CrateTarget::Examples)]
+    #[test_case("tests/integration.rs", CrateTarget::Tests)]
+    #[test_case("tests", CrateTarget::Source)]
+    fn test_crate_target(path: &str, expected: CrateTarget) {
+        let actual = CrateTarget::of(Path::new(path));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("*", "anything", true)]
+    #[test_case("projfetch-*", "projfetch-core", true)]
+    #[test_case("projfetch-*", "core", false)]
+    #[test_case("crate?", "crate1", true)]
+    #[test_case("crate?", "crate12", false)]
+    #[test_case("*-macros", "serde-macros", true)]
+    fn test_wildcard_match(pattern: &str, name: &str, expected: bool) {
+        let actual = wildcard_match(pattern, name);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_group_by_crate() {
+        let file = FileReport::with_code;
+        let files = [
+            file("/ws/crates/core/src/lib.rs", Language::Rust, 100),
+            file("/ws/crates/core/build.rs", Language::Rust, 10),
+            file("/ws/crates/core/tests/it.rs", Language::Rust, 30),
+            file("/ws/crates/cli/src/main.rs", Language::Rust, 20),
+            file("/ws/scripts/release.sh", Language::Shell, 5),
+        ];
+        let crates = [
+            Crate {
+                name: String::from("cli"),
+                root: PathBuf::from("/ws/crates/cli"),
+            },
+            Crate {
+                name: String::from("core"),
+                root: PathBuf::from("/ws/crates/core"),
+            },
+        ];
+
+        let reports = group_by_crate(&files, &crates, Path::new("/ws"));
+        let actual = reports
+            .iter()
+            .map(|report| {
+                (
+                    report.name.as_str(),
+                    report.code(),
+                    report.target_code(CrateTarget::Build),
+                    report.target_code(CrateTarget::Tests),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("core", 140, 10, 30),
+                ("cli", 20, 0, 0),
+                ("(workspace)", 5, 0, 0)
+            ]
+        );
+    }
+}
@@ -1,6 +1,28 @@
-use std::path::Path;
+use std::{
+    fmt::Display,
+    io::{Read, Seek, SeekFrom},
+    ops::AddAssign,
+    path::Path,
+};
 
-use crate::lang::Language;
+use crate::lang::{CustomLanguage, Language};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    pub total: usize,
+}
+
+impl AddAssign for LineStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.code += rhs.code;
+        self.comments += rhs.comments;
+        self.blanks += rhs.blanks;
+        self.total += rhs.total;
+    }
+}
 
 pub struct CodeReader {
     contents: String,
@@ -8,176 +30,677 @@ pub struct CodeReader {
 }
 
 impl CodeReader {
-    #[allow(dead_code)]
     pub fn new(contents: String, lang: Language) -> CodeReader {
         CodeReader { contents, lang }
     }
 
-    pub fn from_path(path: &Path, lang: Language) -> std::io::Result<CodeReader> {
-        let contents = std::fs::read_to_string(path)?;
-
-        Ok(CodeReader { contents, lang })
-    }
-
-    pub fn sloc(&self) -> usize {
+    pub fn stats(&self) -> LineStats {
         let lines = self.contents.lines();
 
-        let mut sloc = 0;
-
-        let comment_token = comment_token(self.lang);
-        let (block_comment_start, block_comment_end) = match block_comment_tokens(self.lang) {
-            Some((start, end)) => (Some(start), Some(end)),
-            None => (None, None),
-        };
-
-        let mut in_block_comment = false;
+        let mut stats = LineStats::default();
+        let mut tokenizer = Tokenizer::new(self.lang);
 
         for line in lines {
+            stats.total += 1;
+
             // COBOL is weird and columns matter
             if matches!(self.lang, Language::Cobol) {
                 // Indicator column is column 7 (1-indexed)
                 const INDICATOR_COLUMN: usize = 6;
 
-                if let Some(ch) = line.chars().nth(INDICATOR_COLUMN) {
-                    if ch == '*' || ch == '/' {
-                        continue;
-                    }
+                if let Some(ch) = line.chars().nth(INDICATOR_COLUMN)
+                    && (ch == '*' || ch == '/')
+                {
+                    stats.comments += 1;
+                    continue;
                 }
             }
 
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
+            match tokenizer.scan_line(line) {
+                LineKind::Blank => stats.blanks += 1,
+                LineKind::Comment => stats.comments += 1,
+                LineKind::Code => stats.code += 1,
             }
+        }
+
+        stats
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(std::io::Error),
+    Binary,
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Binary => write!(f, "binary file"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(e: std::io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+/// Reads a source file, decoding UTF-16 and Latin-1 files as well as UTF-8
+pub fn read_source(path: &Path) -> Result<String, ReadError> {
+    let mut file = std::fs::File::open(path)?;
+
+    // Check the start of the file before reading all of a potentially large binary
+    let mut bytes = Vec::new();
+    file.by_ref()
+        .take(BINARY_CHECK_LENGTH as u64)
+        .read_to_end(&mut bytes)?;
+    if is_binary(&bytes) {
+        return Err(ReadError::Binary);
+    }
+    file.read_to_end(&mut bytes)?;
+
+    decode(&bytes)
+}
+
+/// Reads only the start and end of a source file, enough to look for modelines and a shebang
+pub fn read_source_ends(path: &Path, length: usize) -> Result<String, ReadError> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut bytes = Vec::new();
+    if file.metadata()?.len() <= 2 * length as u64 {
+        file.read_to_end(&mut bytes)?;
+        return decode(&bytes);
+    }
+
+    let mut head = vec![0; length];
+    file.read_exact(&mut head)?;
+    let mut tail = vec![0; length];
+    file.seek(SeekFrom::End(-(length as i64)))?;
+    file.read_exact(&mut tail)?;
+
+    join_ends(&head, &tail)
+}
+
+/// Same as `read_source_ends` for source that was read from somewhere other than a file
+pub fn source_ends(bytes: &[u8], length: usize) -> Result<String, ReadError> {
+    if bytes.len() <= 2 * length {
+        return decode(bytes);
+    }
+
+    join_ends(&bytes[..length], &bytes[bytes.len() - length..])
+}
+
+fn join_ends(head: &[u8], tail: &[u8]) -> Result<String, ReadError> {
+    let head = decode(head)?;
+    let tail = decode(tail)?;
+    // Leave out the partial line the tail starts with
+    let tail = tail.split_once('\n').map_or("", |(_, rest)| rest);
+
+    Ok(format!("{}\n{}", head, tail))
+}
+
+// Same heuristic as git, text files don't contain NUL bytes near the start
+const BINARY_CHECK_LENGTH: usize = 8000;
+
+fn is_binary(bytes: &[u8]) -> bool {
+    let is_utf16 = bytes.starts_with(b"\xff\xfe") || bytes.starts_with(b"\xfe\xff");
+
+    !is_utf16 && bytes[..bytes.len().min(BINARY_CHECK_LENGTH)].contains(&0)
+}
+
+/// Decodes source that was read from somewhere other than a file, e.g. a git blob
+pub fn decode(bytes: &[u8]) -> Result<String, ReadError> {
+    if let Some(bytes) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        return Ok(String::from_utf8_lossy(bytes).into_owned());
+    }
+    if let Some(bytes) = bytes.strip_prefix(b"\xff\xfe") {
+        return Ok(decode_utf16(bytes, u16::from_le_bytes));
+    }
+    if let Some(bytes) = bytes.strip_prefix(b"\xfe\xff") {
+        return Ok(decode_utf16(bytes, u16::from_be_bytes));
+    }
+
+    if is_binary(bytes) {
+        return Err(ReadError::Binary);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(contents) => Ok(contents.to_string()),
+        // Every byte is a valid Latin-1 character
+        Err(_) => Ok(bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Removes comments from source code, keeping line breaks so lines still match up
+pub fn strip_comments(contents: &str, lang: Language) -> String {
+    let mut tokenizer = Tokenizer::new(lang);
+    tokenizer.code = Some(String::with_capacity(contents.len()));
+
+    for line in contents.lines() {
+        tokenizer.scan_line(line);
+    }
+
+    tokenizer.code.unwrap()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Code,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Code,
+    /// Index of the block comment syntax that opened the comment, and its nesting depth
+    BlockComment {
+        index: usize,
+        depth: usize,
+    },
+    /// Index of the string syntax that opened the string
+    String(usize),
+}
+
+/// Tracks string literals and comments through a file, one line at a time
+struct Tokenizer {
+    line_comments: &'static [&'static str],
+    block_comments: &'static [BlockComment],
+    strings: &'static [StringSyntax],
+    state: State,
+    /// Collects everything outside of comments when set
+    code: Option<String>,
+}
+
+impl Tokenizer {
+    fn new(lang: Language) -> Tokenizer {
+        if let Some(custom) = lang.custom() {
+            return Tokenizer::custom(custom);
+        }
+
+        Tokenizer {
+            line_comments: line_comment_tokens(lang),
+            block_comments: block_comment_tokens(lang),
+            strings: string_syntaxes(lang),
+            state: State::Code,
+            code: None,
+        }
+    }
+
+    fn custom(custom: &'static CustomLanguage) -> Tokenizer {
+        Tokenizer {
+            line_comments: custom.line_comments,
+            block_comments: custom.block_comments,
+            strings: custom.strings,
+            state: State::Code,
+            code: None,
+        }
+    }
+
+    fn emit(&mut self, text: &str) {
+        if let Some(code) = self.code.as_mut() {
+            code.push_str(text);
+        }
+    }
+
+    fn scan_line(&mut self, line: &str) -> LineKind {
+        let kind = self.scan_line_contents(line);
+        self.emit("\n");
+
+        kind
+    }
 
-            // Block comments
-            if let Some(ref block_comment_start) = block_comment_start {
-                if !in_block_comment && line.starts_with(block_comment_start.as_str()) {
-                    in_block_comment = true;
+    fn scan_line_contents(&mut self, line: &str) -> LineKind {
+        if line.trim().is_empty() {
+            return LineKind::Blank;
+        }
+
+        let mut has_code = false;
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            let ch = rest.chars().next().unwrap();
+
+            match self.state {
+                State::BlockComment { index, depth } => {
+                    let block = &self.block_comments[index];
+                    if block.nests && rest.starts_with(block.start) {
+                        self.state = State::BlockComment {
+                            index,
+                            depth: depth + 1,
+                        };
+                        i += block.start.len();
+                        continue;
+                    }
+                    if rest.starts_with(block.end) && (!block.anchored || i == 0) {
+                        self.state = match depth {
+                            1 => State::Code,
+                            _ => State::BlockComment {
+                                index,
+                                depth: depth - 1,
+                            },
+                        };
+                        if block.anchored {
+                            // Anchored comments like Perl's POD end on the whole line
+                            break;
+                        }
+                        i += block.end.len();
+                        continue;
+                    }
                 }
-            }
-            if let Some(ref block_comment_end) = block_comment_end {
-                if in_block_comment && line.ends_with(block_comment_end.as_str()) {
-                    in_block_comment = false;
-                    continue;
+                State::String(index) => {
+                    has_code = true;
+                    let string = &self.strings[index];
+                    if string.escapes && ch == '\\' {
+                        // Skip the escaped character
+                        let escape_len = ch.len_utf8()
+                            + line[i + ch.len_utf8()..]
+                                .chars()
+                                .next()
+                                .map_or(0, char::len_utf8);
+                        self.emit(&line[i..i + escape_len]);
+                        i += escape_len;
+                        continue;
+                    }
+                    if rest.starts_with(string.end) {
+                        self.state = State::Code;
+                        self.emit(string.end);
+                        i += string.end.len();
+                        continue;
+                    }
+                    self.emit(&line[i..i + ch.len_utf8()]);
                 }
-            }
-            if in_block_comment {
-                continue;
-            }
-
-            if let Some(ref comment_token) = comment_token {
-                if line.starts_with(comment_token) {
-                    continue;
+                State::Code => {
+                    if let Some((index, block)) =
+                        self.block_comments.iter().enumerate().find(|(_, block)| {
+                            rest.starts_with(block.start)
+                                && (!block.anchored || i == 0)
+                                && (!block.statement || line[..i].trim().is_empty())
+                        })
+                    {
+                        self.state = State::BlockComment { index, depth: 1 };
+                        // Keep tokens on either side of the comment apart
+                        self.emit(" ");
+                        i += block.start.len();
+                        continue;
+                    }
+                    if self
+                        .line_comments
+                        .iter()
+                        .any(|token| rest.starts_with(token))
+                    {
+                        break;
+                    }
+                    if let Some((index, string)) = self
+                        .strings
+                        .iter()
+                        .enumerate()
+                        .find(|(_, string)| rest.starts_with(string.start))
+                    {
+                        self.state = State::String(index);
+                        has_code = true;
+                        self.emit(string.start);
+                        i += string.start.len();
+                        continue;
+                    }
+                    self.emit(&line[i..i + ch.len_utf8()]);
+                    if !ch.is_whitespace() {
+                        has_code = true;
+                    }
                 }
             }
 
-            sloc += 1;
+            i += ch.len_utf8();
         }
 
-        sloc
+        // Most strings only continue onto the next line when the newline is escaped
+        if let State::String(index) = self.state
+            && !self.strings[index].multiline
+            && !line.ends_with('\\')
+        {
+            self.state = State::Code;
+        }
+
+        if has_code {
+            LineKind::Code
+        } else {
+            LineKind::Comment
+        }
     }
 }
 
-fn comment_token(lang: Language) -> Option<String> {
-    Some(
-        match lang {
-            Language::CMake
-            | Language::Dockerfile
-            | Language::Fish
-            | Language::Gherkin
-            | Language::Hcl
-            | Language::Julia
-            | Language::Makefile
-            | Language::Meson
-            | Language::Nim
-            | Language::Nix
-            | Language::Nu
-            | Language::Perl
-            | Language::Powershell
-            | Language::Python
-            | Language::Qml
-            | Language::Ruby
-            | Language::Shell => "#",
-            Language::Antlr
-            | Language::C
-            | Language::CPlusPlus
-            | Language::CSharp
-            | Language::D
-            | Language::Dart
-            | Language::FSharp
-            | Language::Glsl
-            | Language::Go
-            | Language::Java
-            | Language::JavaScript
-            | Language::Protobuf
-            | Language::React
-            | Language::Rust
-            | Language::TypeScript
-            | Language::V
-            | Language::Zig => "//",
-            #[cfg(feature = "mars-lang")]
-            Language::Mars => "//",
-            Language::Ada | Language::Haskell | Language::Lua | Language::Sql => "--",
-            Language::Cobol => "*>",
-            Language::Fortran => "!",
-            Language::OCaml => "\0", // OCaml doesn't technically have single line comments
-            Language::Php => "//",   // FIXME: '#' is also valid
-            Language::VisualBasic => "'",
-            _ => return None,
+#[derive(Clone, Copy, Debug)]
+pub struct BlockComment {
+    pub start: &'static str,
+    pub end: &'static str,
+    /// Can contain other comments with the same delimiters
+    pub nests: bool,
+    /// Delimiters are only recognized at the start of a line
+    pub anchored: bool,
+    /// Only a comment when it's the first text on its line, e.g. a Python docstring. Anywhere
+    /// else the same delimiters are left to the string syntaxes.
+    pub statement: bool,
+}
+
+impl BlockComment {
+    pub const fn new(start: &'static str, end: &'static str) -> BlockComment {
+        BlockComment {
+            start,
+            end,
+            nests: false,
+            anchored: false,
+            statement: false,
         }
-        .into(),
-    )
+    }
+
+    pub const fn nested(start: &'static str, end: &'static str) -> BlockComment {
+        BlockComment {
+            nests: true,
+            ..BlockComment::new(start, end)
+        }
+    }
+
+    const fn anchored(start: &'static str, end: &'static str) -> BlockComment {
+        BlockComment {
+            anchored: true,
+            ..BlockComment::new(start, end)
+        }
+    }
+
+    const fn statement(start: &'static str, end: &'static str) -> BlockComment {
+        BlockComment {
+            statement: true,
+            ..BlockComment::new(start, end)
+        }
+    }
+}
+
+const C_BLOCK: BlockComment = BlockComment::new("/*", "*/");
+const C_NESTED_BLOCK: BlockComment = BlockComment::nested("/*", "*/");
+const CMAKE_BLOCK: BlockComment = BlockComment::new("#[[", "]]");
+const D_NESTED_BLOCK: BlockComment = BlockComment::nested("/+", "+/");
+const HASKELL_BLOCK: BlockComment = BlockComment::nested("{-", "-}");
+const JULIA_BLOCK: BlockComment = BlockComment::nested("#=", "=#");
+const LUA_BLOCK: BlockComment = BlockComment::new("--[[", "]]");
+const MATLAB_BLOCK: BlockComment = BlockComment::nested("%{", "%}");
+const ML_BLOCK: BlockComment = BlockComment::nested("(*", "*)");
+const NIM_BLOCK: BlockComment = BlockComment::nested("#[", "]#");
+const PERL_POD: BlockComment = BlockComment::anchored("=", "=cut");
+const POWERSHELL_BLOCK: BlockComment = BlockComment::new("<#", "#>");
+const PYTHON_SINGLE_QUOTE_BLOCK: BlockComment = BlockComment::statement("'''", "'''");
+const PYTHON_DOUBLE_QUOTE_BLOCK: BlockComment = BlockComment::statement("\"\"\"", "\"\"\"");
+const RUBY_BLOCK: BlockComment = BlockComment::anchored("=begin", "=end");
+const XML_BLOCK: BlockComment = BlockComment::new("<!--", "-->");
+
+#[derive(Clone, Copy, Debug)]
+pub struct StringSyntax {
+    pub start: &'static str,
+    pub end: &'static str,
+    /// Continues onto the next line without escaping the newline
+    pub multiline: bool,
+    /// A backslash escapes the next character
+    pub escapes: bool,
+}
+
+impl StringSyntax {
+    const fn new(delimiter: &'static str) -> StringSyntax {
+        StringSyntax {
+            start: delimiter,
+            end: delimiter,
+            multiline: false,
+            escapes: true,
+        }
+    }
+
+    const fn multiline(start: &'static str, end: &'static str) -> StringSyntax {
+        StringSyntax {
+            start,
+            end,
+            multiline: true,
+            escapes: true,
+        }
+    }
+
+    const fn raw(start: &'static str, end: &'static str) -> StringSyntax {
+        StringSyntax {
+            escapes: false,
+            ..StringSyntax::multiline(start, end)
+        }
+    }
+
+    /// A literal that contains a string delimiter but doesn't start a string, e.g. `'"'`
+    const fn literal(token: &'static str) -> StringSyntax {
+        StringSyntax {
+            start: token,
+            end: "",
+            multiline: false,
+            escapes: false,
+        }
+    }
+}
+
+const DOUBLE_QUOTE: StringSyntax = StringSyntax::new("\"");
+const SINGLE_QUOTE: StringSyntax = StringSyntax::new("'");
+const MULTILINE_DOUBLE_QUOTE: StringSyntax = StringSyntax::multiline("\"", "\"");
+const MULTILINE_SINGLE_QUOTE: StringSyntax = StringSyntax::multiline("'", "'");
+const RAW_SINGLE_QUOTE: StringSyntax = StringSyntax::raw("'", "'");
+const TRIPLE_DOUBLE_QUOTE: StringSyntax = StringSyntax::multiline("\"\"\"", "\"\"\"");
+const TRIPLE_SINGLE_QUOTE: StringSyntax = StringSyntax::multiline("'''", "'''");
+const RAW_TRIPLE_DOUBLE_QUOTE: StringSyntax = StringSyntax::raw("\"\"\"", "\"\"\"");
+const BACKTICK: StringSyntax = StringSyntax::multiline("`", "`");
+const RAW_BACKTICK: StringSyntax = StringSyntax::raw("`", "`");
+const CPP_RAW_STRING: StringSyntax = StringSyntax::raw("R\"(", ")\"");
+const CSHARP_VERBATIM_STRING: StringSyntax = StringSyntax::raw("@\"", "\"");
+const NIX_INDENTED_STRING: StringSyntax = StringSyntax::raw("''", "''");
+const RUST_RAW_STRING: StringSyntax = StringSyntax::raw("r\"", "\"");
+const RUST_HASHED_RAW_STRING: StringSyntax = StringSyntax::raw("r#\"", "\"#");
+const RUST_QUOTE_CHAR: StringSyntax = StringSyntax::literal("'\"'");
+const RUST_ESCAPED_QUOTE_CHAR: StringSyntax = StringSyntax::literal("'\\\"'");
+
+fn line_comment_tokens(lang: Language) -> &'static [&'static str] {
+    match lang {
+        Language::CMake
+        | Language::Dockerfile
+        | Language::Fish
+        | Language::Gherkin
+        | Language::Hcl
+        | Language::Julia
+        | Language::Makefile
+        | Language::Meson
+        | Language::Nim
+        | Language::Nix
+        | Language::Nu
+        | Language::Perl
+        | Language::Powershell
+        | Language::Python
+        | Language::Qml
+        | Language::Ruby
+        | Language::Shell => &["#"],
+        Language::Antlr
+        | Language::C
+        | Language::CPlusPlus
+        | Language::CSharp
+        | Language::D
+        | Language::Dart
+        | Language::FSharp
+        | Language::Glsl
+        | Language::Go
+        | Language::Java
+        | Language::JavaScript
+        | Language::ObjectiveC
+        | Language::Protobuf
+        | Language::React
+        | Language::Rust
+        | Language::TypeScript
+        | Language::V
+        | Language::Verilog
+        | Language::Zig => &["//"],
+        #[cfg(feature = "mars-lang")]
+        Language::Mars => &["//"],
+        Language::Ada | Language::Haskell | Language::Lua | Language::Sql => &["--"],
+        Language::Cobol => &["*>"],
+        Language::Fortran => &["!"],
+        Language::Matlab | Language::Prolog => &["%"],
+        Language::Php => &["//", "#"],
+        Language::VisualBasic => &["'"],
+        // OCaml doesn't technically have single line comments
+        _ => &[],
+    }
 }
 
-fn block_comment_tokens(lang: Language) -> Option<(String, String)> {
-    let tokens = match lang {
+fn block_comment_tokens(lang: Language) -> &'static [BlockComment] {
+    match lang {
         Language::C
         | Language::CPlusPlus
         | Language::CSharp
         | Language::Css
-        | Language::Dart
         | Language::Glsl
         | Language::Go
         | Language::Java
         | Language::JavaScript
         | Language::Nix
+        | Language::ObjectiveC
         | Language::Php
+        | Language::Prolog
         | Language::Qml
         | Language::React
         | Language::Sql
         | Language::TypeScript
-        | Language::V => ("/*", "*/"),
+        | Language::V
+        | Language::Verilog => &[C_BLOCK],
         #[cfg(feature = "mars-lang")]
-        Language::Mars => ("/*", "*/"),
-        Language::CMake => ("#[[", "]]"),
-        Language::FSharp | Language::OCaml => ("(*", "*)"),
-        Language::Julia => ("#=", "=#"),
-        Language::Lua => ("--[[", "]]--"),
-        Language::Nim => ("#[", "]#"),
-        Language::Perl => ("=", "=cut"),
-        Language::Powershell => ("<#", "#>"),
-        Language::Python => ("'''", "'''"), // FIXME: '"""' is also valid
-        Language::Ruby => ("=begin", "=end"),
-
-        // FIXME: D has multiple different ways to comment out code
-        // TBD if I care enough to handle all those cases
-        Language::D => ("/*", "*/"),
+        Language::Mars => &[C_BLOCK],
+        Language::Dart | Language::Rust => &[C_NESTED_BLOCK],
+        Language::CMake => &[CMAKE_BLOCK],
+        Language::D => &[C_BLOCK, D_NESTED_BLOCK],
+        Language::FSharp | Language::OCaml => &[ML_BLOCK],
+        Language::Haskell => &[HASKELL_BLOCK],
+        Language::Julia => &[JULIA_BLOCK],
+        Language::Lua => &[LUA_BLOCK],
+        Language::Matlab => &[MATLAB_BLOCK],
+        Language::Nim => &[NIM_BLOCK],
+        Language::Perl => &[PERL_POD],
+        Language::Powershell => &[POWERSHELL_BLOCK],
+        Language::Python => &[PYTHON_SINGLE_QUOTE_BLOCK, PYTHON_DOUBLE_QUOTE_BLOCK],
+        Language::Ruby => &[RUBY_BLOCK],
         Language::Astro
         | Language::Html
         | Language::Razor
         | Language::Svelte
         | Language::Vue
-        | Language::Xaml => ("<!--", "-->"),
-        _ => return None,
-    };
+        | Language::Xaml => &[XML_BLOCK],
+        _ => &[],
+    }
+}
 
-    Some((tokens.0.into(), tokens.1.into()))
+fn string_syntaxes(lang: Language) -> &'static [StringSyntax] {
+    // Longer delimiters come first, so `"""` isn't taken for an empty string
+    match lang {
+        Language::C
+        | Language::Lua
+        | Language::Nu
+        | Language::ObjectiveC
+        | Language::Powershell => &[DOUBLE_QUOTE, SINGLE_QUOTE],
+        Language::Fish | Language::Perl | Language::Php | Language::Ruby | Language::Sql => {
+            &[MULTILINE_DOUBLE_QUOTE, MULTILINE_SINGLE_QUOTE]
+        }
+        // Backslashes are kept as they are in single quotes, so '\' is a whole string
+        Language::Shell => &[MULTILINE_DOUBLE_QUOTE, RAW_SINGLE_QUOTE],
+        Language::CPlusPlus => &[CPP_RAW_STRING, DOUBLE_QUOTE, SINGLE_QUOTE],
+        Language::CSharp => &[
+            RAW_TRIPLE_DOUBLE_QUOTE,
+            CSHARP_VERBATIM_STRING,
+            DOUBLE_QUOTE,
+            SINGLE_QUOTE,
+        ],
+        Language::Dart => &[
+            TRIPLE_DOUBLE_QUOTE,
+            TRIPLE_SINGLE_QUOTE,
+            DOUBLE_QUOTE,
+            SINGLE_QUOTE,
+        ],
+        // Triple quoted strings that start a statement are docstrings, counted as block comments
+        Language::Python => &[
+            TRIPLE_DOUBLE_QUOTE,
+            TRIPLE_SINGLE_QUOTE,
+            DOUBLE_QUOTE,
+            SINGLE_QUOTE,
+        ],
+        Language::Java | Language::Julia => &[TRIPLE_DOUBLE_QUOTE, DOUBLE_QUOTE, SINGLE_QUOTE],
+        Language::Nim => &[RAW_TRIPLE_DOUBLE_QUOTE, DOUBLE_QUOTE, SINGLE_QUOTE],
+        Language::Go => &[RAW_BACKTICK, DOUBLE_QUOTE, SINGLE_QUOTE],
+        Language::JavaScript | Language::React | Language::TypeScript | Language::Qml => {
+            &[BACKTICK, DOUBLE_QUOTE, SINGLE_QUOTE]
+        }
+        Language::Nix => &[NIX_INDENTED_STRING, MULTILINE_DOUBLE_QUOTE],
+        Language::Rust => &[
+            RUST_HASHED_RAW_STRING,
+            RUST_RAW_STRING,
+            RUST_QUOTE_CHAR,
+            RUST_ESCAPED_QUOTE_CHAR,
+            MULTILINE_DOUBLE_QUOTE,
+        ],
+        // Single quotes are used for lifetimes, type variables and primes in these languages
+        _ => &[DOUBLE_QUOTE],
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use test_case::test_case;
+
+    #[test_case(b"int x;\n", "int x;\n" ; "utf8")]
+    #[test_case(b"\xef\xbb\xbfint x;\n", "int x;\n" ; "utf8 bom")]
+    #[test_case(b"\xff\xfei\0n\0t\0", "int" ; "utf16 le")]
+    #[test_case(b"\xfe\xff\0i\0n\0t", "int" ; "utf16 be")]
+    #[test_case(b"caf\xe9", "café" ; "latin1")]
+    fn test_decode(bytes: &[u8], expected: &str) {
+        let actual = decode(bytes).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case(b"#!/bin/sh\necho hi\n", "#!/bin/sh\necho hi\n" ; "short source")]
+    #[test_case(b"#!/bin/sh\n0123456789\nabcdefghij\n# vim: ft=sh\n", "#!/bin/sh\n012345\n# vim: ft=sh\n" ; "long source")]
+    fn test_source_ends(bytes: &[u8], expected: &str) {
+        let actual = source_ends(bytes, 16).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let actual = decode(b"\x7fELF\x02\x01\x01\0\0\0");
+
+        assert!(matches!(actual, Err(ReadError::Binary)));
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let contents =
+            "int x; // trailing\n/* block\n still */ int/**/y;\nchar *s = \"/* kept */\";\n";
+
+        let expected = "int x; \n \n int y;\nchar *s = \"/* kept */\";\n";
+
+        let actual = strip_comments(contents, Language::C);
+
+        assert_eq!(actual, expected);
+    }
 
     #[test]
     fn test_simple() {
@@ -196,7 +719,7 @@ int main(void) {
         let expected = 5;
 
         let reader = CodeReader::new(contents, Language::C);
-        let actual = reader.sloc();
+        let actual = reader.stats().code;
 
         assert_eq!(actual, expected);
     }
@@ -223,7 +746,7 @@ public class Program
         let expected = 9;
 
         let reader = CodeReader::new(contents, Language::CSharp);
-        let actual = reader.sloc();
+        let actual = reader.stats().code;
 
         assert_eq!(actual, expected);
     }
@@ -248,7 +771,37 @@ int main() {
         let expected = 5;
 
         let reader = CodeReader::new(contents, Language::CPlusPlus);
-        let actual = reader.sloc();
+        let actual = reader.stats().code;
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_stats() {
+        let contents = String::from(
+            r#"
+#include <iostream>
+
+int main() {
+    /*
+     * C++ uses overloaded bit shift operators to print to output streams
+     */
+    std::cout << "Hello, World" << std::endl; // Print output
+
+    // Exit
+    return 0;
+}"#,
+        );
+
+        let expected = LineStats {
+            code: 5,
+            comments: 4,
+            blanks: 3,
+            total: 12,
+        };
+
+        let reader = CodeReader::new(contents, Language::CPlusPlus);
+        let actual = reader.stats();
 
         assert_eq!(actual, expected);
     }
@@ -265,7 +818,252 @@ let () = Printf.printf "%s\n" "Hello, World!"
         let expected = 1;
 
         let reader = CodeReader::new(contents, Language::OCaml);
-        let actual = reader.sloc();
+        let actual = reader.stats().code;
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_trailing_block_comment() {
+        let contents = String::from(
+            r#"
+int x; /* start of a comment
+   still commenting
+   the end */ int y;
+/* just a comment */
+int z; /* another comment */
+"#,
+        );
+
+        let expected = LineStats {
+            code: 3,
+            comments: 2,
+            blanks: 1,
+            total: 6,
+        };
+
+        let reader = CodeReader::new(contents, Language::C);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_comment_tokens_in_string() {
+        let contents = String::from(
+            r#"
+const char *open = "/* not a comment";
+const char *line = "// not a comment either";
+char quote = '"';
+int x = 0;
+/* a real comment */
+"#,
+        );
+
+        let expected = LineStats {
+            code: 4,
+            comments: 1,
+            blanks: 1,
+            total: 6,
+        };
+
+        let reader = CodeReader::new(contents, Language::C);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_escaped_quote_in_string() {
+        let contents = String::from(
+            r#"
+String s = "escaped \" /* still a string";
+// comment
+"#,
+        );
+
+        let expected = LineStats {
+            code: 1,
+            comments: 1,
+            blanks: 1,
+            total: 3,
+        };
+
+        let reader = CodeReader::new(contents, Language::Java);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_anchored_block_comment() {
+        let contents = String::from(
+            r#"
+my $x = 1;
+=pod
+
+Documentation
+
+=cut
+print $x;
+"#,
+        );
+
+        let expected = LineStats {
+            code: 2,
+            comments: 3,
+            blanks: 3,
+            total: 8,
+        };
+
+        let reader = CodeReader::new(contents, Language::Perl);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_nested_block_comment() {
+        let contents = String::from(
+            r#"
+/* outer /* inner */
+   still commented */
+fn main() {}
+"#,
+        );
+
+        let expected = LineStats {
+            code: 1,
+            comments: 2,
+            blanks: 1,
+            total: 4,
+        };
+
+        let reader = CodeReader::new(contents, Language::Rust);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_nested_block_comment_haskell() {
+        let contents = String::from(
+            r#"
+{- outer
+   {- inner -}
+   still commented -}
+main = putStrLn "Hello, World!" -- greet
+"#,
+        );
+
+        let expected = LineStats {
+            code: 1,
+            comments: 3,
+            blanks: 1,
+            total: 5,
+        };
+
+        let reader = CodeReader::new(contents, Language::Haskell);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unnested_block_comment() {
+        let contents = String::from(
+            r#"
+/* outer /* inner */
+int x;
+"#,
+        );
+
+        let expected = LineStats {
+            code: 1,
+            comments: 1,
+            blanks: 1,
+            total: 3,
+        };
+
+        let reader = CodeReader::new(contents, Language::C);
+        let actual = reader.stats();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case(Language::Php, "<?php\n// comment\n# also a comment\necho 'hi'; # trailing\n/* block */", 2, 3 ; "php")]
+    #[test_case(Language::Python, "query = \"\"\"\nSELECT *\nFROM t\nWHERE x = 1\n\"\"\"", 5, 0 ; "python assigned string")]
+    #[test_case(Language::Python, "def f():\n    \"\"\"Docstring.\n    More.\n    \"\"\"\n    return f'''\n# kept\n'''", 4, 3 ; "python docstring and string")]
+    #[test_case(Language::Python, "'''\nsingle\n'''\n\"\"\"\ndouble\n\"\"\"\nprint('hi')  # greet", 1, 6 ; "python")]
+    #[test_case(Language::D, "/+ outer /+ inner +/\nstill +/\n/* plain */\nint x; // trailing", 1, 3 ; "d")]
+    #[test_case(Language::Lua, "--[[\nblock\n]]\n-- line\nprint('hi')", 1, 4 ; "lua")]
+    #[test_case(Language::CMake, "#[[\nblock\n]]\n# line\nproject(foo)", 1, 4 ; "cmake")]
+    #[test_case(Language::Ruby, "=begin\nblock\n=end\n# line\nputs 'hi'", 1, 4 ; "ruby")]
+    #[test_case(Language::Html, "<!--\nblock\n-->\n<p>hi</p> <!-- trailing -->", 1, 3 ; "html")]
+    fn test_comment_syntaxes(lang: Language, contents: &str, code: usize, comments: usize) {
+        let reader = CodeReader::new(contents.to_string(), lang);
+        let actual = reader.stats();
+
+        assert_eq!((actual.code, actual.comments), (code, comments));
+    }
+
+    #[test_case(Language::JavaScript, "const s = `\n// not a comment\n/* nor this\n`;\n// real", 4, 1 ; "template literal")]
+    #[test_case(Language::Go, "s := `\n// kept \\`\n// real", 2, 1 ; "go raw string")]
+    #[test_case(Language::Rust, "let s = \"first\n// still a string\";\n// real", 2, 1 ; "rust string")]
+    #[test_case(Language::Rust, "let s = r#\"\n/* \"quoted\" */\n\"#;\n// real", 3, 1 ; "rust raw string")]
+    #[test_case(Language::Rust, "let q = '\"';\nlet e = '\\\"';\n// real", 2, 1 ; "rust quote char")]
+    #[test_case(Language::Java, "String s = \"\"\"\n    // text\n    \"\"\";\n// real", 3, 1 ; "java text block")]
+    #[test_case(Language::CPlusPlus, "auto s = R\"(\n// kept\n)\";\n// real", 3, 1 ; "cpp raw string")]
+    #[test_case(Language::Nix, "''\n# kept\n''\n# real", 3, 1 ; "nix indented string")]
+    #[test_case(Language::Shell, "echo \"first\n# inside the string\nlast\"\n# real", 3, 1 ; "shell double quotes")]
+    #[test_case(Language::Shell, "echo '\\'\n# real\necho 'a\n# kept\n'", 4, 1 ; "shell single quotes")]
+    #[test_case(Language::Php, "$sql = \"SELECT *\n# inside the string\nFROM t\";\n# real", 3, 1 ; "php string")]
+    #[test_case(Language::C, "char *s = \"open\n// comment", 1, 1 ; "unterminated string ends with the line")]
+    fn test_multiline_strings(lang: Language, contents: &str, code: usize, comments: usize) {
+        let reader = CodeReader::new(contents.to_string(), lang);
+        let actual = reader.stats();
+
+        assert_eq!((actual.code, actual.comments), (code, comments));
+    }
+
+    #[test]
+    fn test_custom_language() {
+        static TEMPLATE: CustomLanguage = CustomLanguage {
+            name: "Template",
+            line_comments: &[";;"],
+            block_comments: &[BlockComment::nested("{{", "}}")],
+            strings: &[StringSyntax::raw("`", "`")],
+            color: termcolor::Color::Rgb(0, 0, 0),
+        };
+
+        let contents = r#"
+;; line comment
+{{ block {{ nested }}
+   comment }}
+render page
+render `text
+;; kept
+`
+"text ;; comment
+"#;
+
+        let expected = [
+            LineKind::Blank,
+            LineKind::Comment,
+            LineKind::Comment,
+            LineKind::Comment,
+            LineKind::Code,
+            LineKind::Code,
+            LineKind::Code,
+            LineKind::Code,
+            LineKind::Code,
+        ];
+
+        let mut tokenizer = Tokenizer::custom(&TEMPLATE);
+        let actual = contents
+            .lines()
+            .map(|line| tokenizer.scan_line(line))
+            .collect::<Vec<_>>();
 
         assert_eq!(actual, expected);
     }
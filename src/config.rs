@@ -0,0 +1,417 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use termcolor::Color;
+
+use crate::{
+    comments::{BlockComment, StringSyntax},
+    lang::{CustomLanguage, Language, LanguageMappings},
+};
+
+pub const PROJECT_CONFIG_FILE: &str = ".projfetch.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    Table,
+    Compact,
+    Bar,
+    Fetch,
+    Summary,
+}
+
+/// Defaults for command line options, read from the user and project config files
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub all: Option<bool>,
+    pub count: Option<usize>,
+    pub exclude: Option<Vec<String>>,
+    pub max_width: Option<u16>,
+    pub display: Option<DisplayMode>,
+    pub summary_length: Option<usize>,
+    pub gitignore: Option<bool>,
+    pub tracked: Option<bool>,
+    pub threads: Option<usize>,
+    /// Extensions (without the leading dot) mapped to language names
+    pub extensions: HashMap<String, String>,
+    /// File names mapped to language names
+    pub filenames: HashMap<String, String>,
+    pub languages: Vec<LanguageDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub line_comments: Vec<String>,
+    #[serde(default)]
+    pub block_comments: Vec<BlockCommentDefinition>,
+    /// Without any, quotes are ordinary code and comment delimiters inside them still count
+    #[serde(default)]
+    pub strings: Vec<StringDefinition>,
+    /// Hex color, e.g. "#ff4300"
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockCommentDefinition {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub nests: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StringDefinition {
+    pub start: String,
+    pub end: String,
+    /// Continues onto the next line without escaping the newline
+    #[serde(default)]
+    pub multiline: bool,
+    /// A backslash doesn't escape the next character
+    #[serde(default)]
+    pub raw: bool,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    path: PathBuf,
+    message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.to_string_lossy(), self.message)
+    }
+}
+
+impl Config {
+    /// Loads the user config, then the project config in `root` on top of it
+    pub fn load(root: &Path) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+
+        if let Some(path) = user_config_path() {
+            config = config.merge(Config::from_file(&path)?);
+        }
+        config = config.merge(Config::from_file(&root.join(PROJECT_CONFIG_FILE))?);
+
+        Ok(config)
+    }
+
+    /// Reads a config file, a missing file is treated as empty
+    fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => {
+                return Err(ConfigError {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                });
+            }
+        };
+
+        toml::from_str(&contents).map_err(|e| ConfigError {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })
+    }
+
+    /// Combines two configs, preferring values from `other`
+    fn merge(mut self, other: Config) -> Config {
+        self.extensions.extend(other.extensions);
+        self.filenames.extend(other.filenames);
+        self.languages
+            .retain(|lang| !other.languages.iter().any(|other| other.name == lang.name));
+        self.languages.extend(other.languages);
+
+        Config {
+            all: other.all.or(self.all),
+            count: other.count.or(self.count),
+            exclude: other.exclude.or(self.exclude),
+            max_width: other.max_width.or(self.max_width),
+            display: other.display.or(self.display),
+            summary_length: other.summary_length.or(self.summary_length),
+            gitignore: other.gitignore.or(self.gitignore),
+            tracked: other.tracked.or(self.tracked),
+            threads: other.threads.or(self.threads),
+            extensions: self.extensions,
+            filenames: self.filenames,
+            languages: self.languages,
+        }
+    }
+
+    /// Builds the languages defined in the config, in the order `Language::Custom` indexes them.
+    /// Their strings are leaked since they're needed until the program exits.
+    pub fn custom_languages(&self) -> Result<Vec<CustomLanguage>, String> {
+        self.languages
+            .iter()
+            .map(|definition| {
+                if Language::ALL
+                    .iter()
+                    .any(|lang| lang.to_string().eq_ignore_ascii_case(&definition.name))
+                {
+                    return Err(format!(
+                        "Language \"{}\" is already built in",
+                        definition.name
+                    ));
+                }
+
+                let color = parse_hex_color(&definition.color).ok_or_else(|| {
+                    format!(
+                        "Invalid color \"{}\" for language \"{}\"",
+                        definition.color, definition.name
+                    )
+                })?;
+
+                // Empty delimiters would match everywhere without ever advancing the tokenizer
+                let empty_delimiter = || {
+                    format!(
+                        "Empty comment or string delimiter for language \"{}\"",
+                        definition.name
+                    )
+                };
+
+                let line_comments = definition
+                    .line_comments
+                    .iter()
+                    .map(|token| match token.is_empty() {
+                        true => Err(empty_delimiter()),
+                        false => Ok(&*token.clone().leak()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let block_comments = definition
+                    .block_comments
+                    .iter()
+                    .map(|block| {
+                        if block.start.is_empty() || block.end.is_empty() {
+                            return Err(empty_delimiter());
+                        }
+
+                        let start = &*block.start.clone().leak();
+                        let end = &*block.end.clone().leak();
+                        Ok(if block.nests {
+                            BlockComment::nested(start, end)
+                        } else {
+                            BlockComment::new(start, end)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let strings = definition
+                    .strings
+                    .iter()
+                    .map(|string| {
+                        if string.start.is_empty() || string.end.is_empty() {
+                            return Err(empty_delimiter());
+                        }
+
+                        Ok(StringSyntax {
+                            start: string.start.clone().leak(),
+                            end: string.end.clone().leak(),
+                            multiline: string.multiline,
+                            escapes: !string.raw,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(CustomLanguage {
+                    name: definition.name.clone().leak(),
+                    line_comments: line_comments.leak(),
+                    block_comments: block_comments.leak(),
+                    strings: strings.leak(),
+                    color,
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves the extension and file name mappings, including those of the languages defined
+    /// in the config. Custom languages need to be registered first.
+    pub fn language_mappings(&self) -> Result<LanguageMappings, String> {
+        let lookup = |name: &String| {
+            Language::from_name(name).ok_or_else(|| format!("Unknown language \"{}\"", name))
+        };
+
+        let mut mappings = LanguageMappings::default();
+        for (index, definition) in self.languages.iter().enumerate() {
+            for extension in definition.extensions.iter() {
+                mappings.extensions.insert(
+                    extension.trim_start_matches('.').to_string(),
+                    Language::Custom(index),
+                );
+            }
+            for filename in definition.filenames.iter() {
+                mappings
+                    .filenames
+                    .insert(filename.clone(), Language::Custom(index));
+            }
+        }
+
+        for (filename, name) in self.filenames.iter() {
+            mappings.filenames.insert(filename.clone(), lookup(name)?);
+        }
+        for (extension, name) in self.extensions.iter() {
+            mappings
+                .extensions
+                .insert(extension.trim_start_matches('.').to_string(), lookup(name)?);
+        }
+
+        Ok(mappings)
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ if cfg!(windows) => PathBuf::from(std::env::var_os("APPDATA")?),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_dir.join("projfetch").join("config.toml"))
+}
+
+fn parse_hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_parse() {
+        let contents = r#"
+count = 5
+exclude = ["Makefile"]
+display = "compact"
+
+[extensions]
+inc = "C++"
+".tpl" = "html"
+
+[filenames]
+Justfile = "Makefile"
+"#;
+
+        let config: Config = toml::from_str(contents).unwrap();
+        let mappings = config.language_mappings().unwrap();
+
+        assert_eq!(config.count, Some(5));
+        assert_eq!(config.exclude, Some(vec![String::from("Makefile")]));
+        assert_eq!(config.display, Some(DisplayMode::Compact));
+        assert_eq!(mappings.extensions.get("inc"), Some(&Language::CPlusPlus));
+        assert_eq!(mappings.extensions.get("tpl"), Some(&Language::Html));
+        assert_eq!(
+            mappings.filenames.get("Justfile"),
+            Some(&Language::Makefile)
+        );
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        let contents = r#"
+[extensions]
+inc = "Brainfuck"
+"#;
+
+        let config: Config = toml::from_str(contents).unwrap();
+
+        assert!(config.language_mappings().is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let user: Config =
+            toml::from_str("count = 5\nmax_width = 80\n[extensions]\ninc = \"C\"").unwrap();
+        let project: Config = toml::from_str("count = 3\n[extensions]\ntpl = \"HTML\"").unwrap();
+
+        let config = user.merge(project);
+
+        assert_eq!(config.count, Some(3));
+        assert_eq!(config.max_width, Some(80));
+        assert_eq!(config.extensions.len(), 2);
+    }
+
+    #[test]
+    fn test_custom_languages() {
+        let contents = r##"
+[[languages]]
+name = "Mylang"
+extensions = ["myl"]
+line_comments = [";"]
+block_comments = [{ start = "#|", end = "|#", nests = true }]
+strings = [{ start = "\"", end = "\"", multiline = true }]
+color = "#843a31"
+"##;
+
+        let config: Config = toml::from_str(contents).unwrap();
+        let languages = config.custom_languages().unwrap();
+
+        assert_eq!(languages.len(), 1);
+        assert_eq!(languages[0].name, "Mylang");
+        assert_eq!(languages[0].line_comments, &[";"]);
+        assert_eq!(languages[0].block_comments[0].start, "#|");
+        assert!(languages[0].block_comments[0].nests);
+        assert_eq!(languages[0].strings[0].end, "\"");
+        assert!(languages[0].strings[0].multiline);
+        assert!(languages[0].strings[0].escapes);
+        assert_eq!(languages[0].color, Color::Rgb(132, 58, 49));
+    }
+
+    #[test]
+    fn test_custom_language_shadows_builtin() {
+        let contents = r##"
+[[languages]]
+name = "rust"
+color = "#000000"
+"##;
+
+        let config: Config = toml::from_str(contents).unwrap();
+
+        assert!(config.custom_languages().is_err());
+    }
+
+    #[test_case(r#"line_comments = [""]"# ; "empty line comment")]
+    #[test_case(r#"block_comments = [{ start = "", end = "" }]"# ; "empty block comment")]
+    #[test_case(r##"block_comments = [{ start = "#|", end = "" }]"## ; "empty block comment end")]
+    #[test_case(r#"strings = [{ start = "", end = "'" }]"# ; "empty string start")]
+    fn test_custom_language_empty_delimiter(comments: &str) {
+        let contents = format!(
+            "[[languages]]\nname = \"Mylang\"\n{}\ncolor = \"#000000\"\n",
+            comments
+        );
+
+        let config: Config = toml::from_str(&contents).unwrap();
+
+        assert!(config.custom_languages().is_err());
+    }
+
+    #[test_case("#ff4300", Some(Color::Rgb(255, 67, 0)))]
+    #[test_case("00599C", Some(Color::Rgb(0, 89, 156)))]
+    #[test_case("#fff", None)]
+    #[test_case("#gggggg", None)]
+    fn test_parse_hex_color(hex: &str, expected: Option<Color>) {
+        let actual = parse_hex_color(hex);
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    git,
+    lang::Language,
+    report::FileReport,
+    scan::{Scan, ScanOptions, is_candidate, scan_blobs, scan_directory},
+};
+
+pub struct LanguageDiff {
+    pub lang: Language,
+    pub before: usize,
+    pub after: usize,
+    /// Net lines of code gained by the files that grew, including new files. Lines that were
+    /// rewritten within a file aren't counted.
+    pub growth: usize,
+    /// Net lines of code lost by the files that shrank, including deleted files
+    pub shrinkage: usize,
+}
+
+impl LanguageDiff {
+    fn new(lang: Language) -> LanguageDiff {
+        LanguageDiff {
+            lang,
+            before: 0,
+            after: 0,
+            growth: 0,
+            shrinkage: 0,
+        }
+    }
+
+    pub fn net(&self) -> isize {
+        self.after as isize - self.before as isize
+    }
+}
+
+/// Counts one side of a diff, either a directory or a revision of the repository at `pwd`.
+/// Returns the root that the counted files' paths are under.
+pub fn scan_target(
+    pwd: &Path,
+    target: &str,
+    options: &ScanOptions,
+) -> Result<(PathBuf, Scan), String> {
+    // Directories are relative to the path being counted, like the revisions
+    let path = pwd.join(target);
+    if path.is_dir() {
+        let root = path.canonicalize().map_err(|e| e.to_string())?;
+        let scan = scan_directory(&root, options);
+        return Ok((root, scan));
+    }
+
+    let blobs = git::revision_files(pwd, target, is_candidate)?;
+    Ok((pwd.to_path_buf(), scan_blobs(&blobs, options)))
+}
+
+/// Compares the files of two scans by their path relative to each root. A file that changed
+/// language counts as removed from the old language and added to the new one.
+pub fn diff_files(
+    before: &[FileReport],
+    before_root: &Path,
+    after: &[FileReport],
+    after_root: &Path,
+) -> Vec<LanguageDiff> {
+    let relative = |file: &FileReport, root: &Path| {
+        file.path
+            .strip_prefix(root)
+            .unwrap_or(&file.path)
+            .to_path_buf()
+    };
+    let before = before
+        .iter()
+        .map(|file| (relative(file, before_root), (file.lang, file.stats.code)))
+        .collect::<HashMap<_, _>>();
+    let after = after
+        .iter()
+        .map(|file| (relative(file, after_root), (file.lang, file.stats.code)))
+        .collect::<HashMap<_, _>>();
+
+    let mut diffs: HashMap<Language, LanguageDiff> = HashMap::new();
+    for (path, &(lang, code)) in before.iter() {
+        let diff = diffs.entry(lang).or_insert_with(|| LanguageDiff::new(lang));
+        diff.before += code;
+        match after.get(path) {
+            Some(&(after_lang, after_code)) if after_lang == lang => {
+                diff.shrinkage += code.saturating_sub(after_code)
+            }
+            _ => diff.shrinkage += code,
+        }
+    }
+    for (path, &(lang, code)) in after.iter() {
+        let diff = diffs.entry(lang).or_insert_with(|| LanguageDiff::new(lang));
+        diff.after += code;
+        match before.get(path) {
+            Some(&(before_lang, before_code)) if before_lang == lang => {
+                diff.growth += code.saturating_sub(before_code)
+            }
+            _ => diff.growth += code,
+        }
+    }
+
+    let mut diffs = diffs.into_values().collect::<Vec<_>>();
+    diffs.sort_by(|a, b| {
+        b.after
+            .cmp(&a.after)
+            .then(b.before.cmp(&a.before))
+            .then(a.lang.to_string().cmp(&b.lang.to_string()))
+    });
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_files() {
+        let file = FileReport::with_code;
+        let before = [
+            file("/old/main.js", Language::JavaScript, 100),
+            file("/old/util.js", Language::JavaScript, 50),
+            file("/old/lib.rs", Language::Rust, 20),
+            file("/old/gone.py", Language::Python, 10),
+        ];
+        let after = [
+            file("/new/main.js", Language::JavaScript, 120),
+            file("/new/util.ts", Language::TypeScript, 60),
+            file("/new/lib.rs", Language::Rust, 5),
+        ];
+
+        let diffs = diff_files(&before, Path::new("/old"), &after, Path::new("/new"));
+        let actual = diffs
+            .iter()
+            .map(|d| (d.lang, d.before, d.after, d.growth, d.shrinkage, d.net()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                (Language::JavaScript, 150, 120, 20, 50, -30),
+                (Language::TypeScript, 0, 60, 60, 0, 60),
+                (Language::Rust, 20, 5, 0, 15, -15),
+                (Language::Python, 10, 0, 0, 10, -10),
+            ]
+        );
+    }
+}
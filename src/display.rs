@@ -1,14 +1,1043 @@
-use std::path::Path;
+use std::{io::IsTerminal, path::Path};
 
-use crossterm::{ExecutableCommand, cursor::MoveToColumn};
-use human_repr::HumanCount;
+use clap::ValueEnum;
+
+use itertools::Itertools;
+use serde::Serialize;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use crate::lang::Language;
+use crate::{
+    cargo::{CrateReport, CrateTarget},
+    comments::LineStats,
+    diff::LanguageDiff,
+    fetch::Fetch,
+    history::HistoryPoint,
+    lang::Language,
+    projects::Project,
+    report::FileReport,
+    scan::SkippedFile,
+    tree::DirNode,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorWhen {
+    /// Color the output when it's a terminal, unless NO_COLOR is set
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn color_choice(when: ColorWhen) -> ColorChoice {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let clicolor_force =
+        std::env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+
+    resolve_color_choice(
+        when,
+        no_color,
+        clicolor_force,
+        std::io::stdout().is_terminal(),
+    )
+}
+
+/// The environment variables only apply in auto mode, NO_COLOR taking priority
+fn resolve_color_choice(
+    when: ColorWhen,
+    no_color: bool,
+    clicolor_force: bool,
+    is_terminal: bool,
+) -> ColorChoice {
+    match when {
+        ColorWhen::Always => ColorChoice::Always,
+        ColorWhen::Never => ColorChoice::Never,
+        ColorWhen::Auto if no_color => ColorChoice::Never,
+        ColorWhen::Auto if clicolor_force => ColorChoice::Always,
+        // termcolor still checks TERM, leaving dumb terminals uncolored
+        ColorWhen::Auto if is_terminal => ColorChoice::Auto,
+        ColorWhen::Auto => ColorChoice::Never,
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport {
+    root: String,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    total: usize,
+    languages: Vec<JsonLanguage>,
+}
+
+#[derive(Serialize)]
+struct JsonLanguage {
+    name: String,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    total: usize,
+    percent: f64,
+    color: String,
+}
+
+pub fn print_results_json(results: Vec<(Language, LineStats)>, pwd: &Path) {
+    let report = json_report(results, pwd);
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+fn json_report(results: Vec<(Language, LineStats)>, root: &Path) -> JsonReport {
+    let mut totals = LineStats::default();
+    for (_, stats) in results.iter() {
+        totals += *stats;
+    }
+
+    JsonReport {
+        root: root.to_string_lossy().into_owned(),
+        code: totals.code,
+        comments: totals.comments,
+        blanks: totals.blanks,
+        total: totals.total,
+        languages: json_languages(results, totals.code),
+    }
+}
+
+fn json_languages(results: Vec<(Language, LineStats)>, total_code: usize) -> Vec<JsonLanguage> {
+    results
+        .into_iter()
+        .map(|(lang, stats)| JsonLanguage {
+            name: lang.to_string(),
+            code: stats.code,
+            comments: stats.comments,
+            blanks: stats.blanks,
+            total: stats.total,
+            percent: percent(stats.code, total_code),
+            color: color_hex(lang.color()),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct JsonProjectsReport {
+    projects: Vec<JsonReport>,
+    total: JsonReport,
+}
+
+pub fn print_projects_json(
+    projects: Vec<Project>,
+    results: Vec<(Language, LineStats)>,
+    pwd: &Path,
+) {
+    let report = JsonProjectsReport {
+        projects: projects
+            .into_iter()
+            .map(|project| json_report(project.languages, &project.root))
+            .collect(),
+        total: json_report(results, pwd),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+/// Prints the languages of every project, followed by the overall total as the "(total)" project
+pub fn print_projects_csv(projects: Vec<Project>, results: Vec<(Language, LineStats)>, pwd: &Path) {
+    let projects = projects
+        .into_iter()
+        .map(|project| (relative_to(&project.root, pwd), project.languages))
+        .chain([(String::from("(total)"), results)]);
+
+    println!("project,language,code,comments,blanks,total,percent,color");
+    for (root, languages) in projects {
+        let total_code = languages.iter().map(|r| r.1.code).sum::<usize>();
+        for (lang, stats) in languages {
+            println!(
+                "{},{},{},{},{},{},{:.2},{}",
+                csv_field(&root),
+                csv_field(&lang.to_string()),
+                stats.code,
+                stats.comments,
+                stats.blanks,
+                stats.total,
+                percent(stats.code, total_code),
+                color_hex(lang.color())
+            );
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonCratesReport {
+    root: String,
+    crates: Vec<JsonCrate>,
+}
+
+#[derive(Serialize)]
+struct JsonCrate {
+    name: String,
+    path: String,
+    code: usize,
+    languages: Vec<JsonLanguage>,
+    targets: Vec<JsonCrateTarget>,
+}
+
+#[derive(Serialize)]
+struct JsonCrateTarget {
+    target: String,
+    code: usize,
+    languages: Vec<JsonLanguage>,
+}
+
+pub fn print_crates_json(crates: Vec<CrateReport>, pwd: &Path) {
+    let report = JsonCratesReport {
+        root: pwd.to_string_lossy().into_owned(),
+        crates: crates
+            .iter()
+            .map(|report| JsonCrate {
+                name: report.name.clone(),
+                path: relative_to(&report.root, pwd),
+                code: report.code(),
+                languages: json_languages(report.languages(), report.code()),
+                targets: report
+                    .targets
+                    .keys()
+                    .map(|&target| JsonCrateTarget {
+                        target: target.to_string(),
+                        code: report.target_code(target),
+                        languages: json_languages(
+                            report.target_languages(target),
+                            report.target_code(target),
+                        ),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+pub fn print_crates_csv(crates: Vec<CrateReport>, pwd: &Path) {
+    println!("crate,path,target,language,code,comments,blanks,total");
+    for report in crates {
+        for &target in report.targets.keys() {
+            for (lang, stats) in report.target_languages(target) {
+                println!(
+                    "{},{},{},{},{},{},{},{}",
+                    csv_field(&report.name),
+                    csv_field(&relative_to(&report.root, pwd)),
+                    target,
+                    csv_field(&lang.to_string()),
+                    stats.code,
+                    stats.comments,
+                    stats.blanks,
+                    stats.total
+                );
+            }
+        }
+    }
+}
+
+/// Prints the code in each crate by target, along with the crate's top languages
+pub fn print_crates(crates: Vec<CrateReport>, pwd: &Path, color: ColorChoice) {
+    if crates.is_empty() {
+        return;
+    }
+
+    println!("Crate breakdown for {:?}:\n", pwd);
+
+    const CRATE_HEADER: &str = "Crate";
+    const CODE_HEADER: &str = "Code";
+    const LANGUAGES_HEADER: &str = "Languages";
+    const LANGUAGES_SHOWN: usize = 3;
+
+    let crate_col_width = crates
+        .iter()
+        .map(|report| report.name.chars().count())
+        .max()
+        .unwrap()
+        .max(CRATE_HEADER.len());
+    let code_col_width = column_width(crates.iter().map(|c| c.code()), CODE_HEADER);
+    let target_col_widths = CrateTarget::ALL
+        .iter()
+        .map(|&target| {
+            let header = capitalize(&target.to_string());
+            let width = column_width(crates.iter().map(|c| c.target_code(target)), &header);
+            (header, width)
+        })
+        .collect::<Vec<_>>();
+
+    print!(
+        "{:<crate_col_width$}  {:>code_col_width$}",
+        CRATE_HEADER, CODE_HEADER
+    );
+    for (header, width) in target_col_widths.iter() {
+        print!("  {:>width$}", header);
+    }
+    println!("  {}", LANGUAGES_HEADER);
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for report in crates {
+        let code = report.code();
+        print!(
+            "{:<crate_col_width$}  {:>code_col_width$}",
+            report.name, code
+        );
+        for (&target, (_, width)) in CrateTarget::ALL.iter().zip(target_col_widths.iter()) {
+            print!("  {:>width$}", report.target_code(target));
+        }
+        print!(" ");
+
+        for (lang, stats) in report.languages().into_iter().take(LANGUAGES_SHOWN) {
+            print!(" {} ", lang);
+            stdstream_stdout
+                .set_color(ColorSpec::new().set_fg(Some(lang.color())))
+                .unwrap();
+            print!("{:.0}%", percent(stats.code, code));
+            stdstream_stdout.reset().unwrap();
+        }
+        println!();
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[derive(Serialize)]
+struct JsonDir {
+    path: String,
+    code: usize,
+    languages: Vec<JsonLanguage>,
+    children: Vec<JsonDir>,
+}
+
+pub fn print_tree_json(tree: DirNode) {
+    fn json_dir(node: &DirNode, path: String) -> JsonDir {
+        JsonDir {
+            code: node.code(),
+            languages: json_languages(node.languages(), node.code()),
+            children: node
+                .children()
+                .into_iter()
+                .map(|child| json_dir(child, format!("{}/{}", path, child.name)))
+                .collect(),
+            path,
+        }
+    }
+
+    let report = json_dir(&tree, tree.name.clone());
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+pub fn print_tree_csv(tree: DirNode) {
+    fn print_dir(node: &DirNode, path: String, depth: usize) {
+        let code = node.code();
+        for (lang, stats) in node.languages() {
+            println!(
+                "{},{},{},{},{},{},{},{:.2},{}",
+                csv_field(&path),
+                depth,
+                csv_field(&lang.to_string()),
+                stats.code,
+                stats.comments,
+                stats.blanks,
+                stats.total,
+                percent(stats.code, code),
+                color_hex(lang.color())
+            );
+        }
+        for child in node.children() {
+            print_dir(child, format!("{}/{}", path, child.name), depth + 1);
+        }
+    }
+
+    println!("path,depth,language,code,comments,blanks,total,percent,color");
+    print_dir(&tree, tree.name.clone(), 0);
+}
+
+/// Prints the directory tree with each directory's code, dominant language and a small bar
+/// stacking its languages
+pub fn print_tree(tree: DirNode, pwd: &Path, color: ColorChoice) {
+    if tree.code() == 0 {
+        return;
+    }
+
+    println!("Directory breakdown for {:?}:\n", pwd);
+
+    const MINI_BAR_WIDTH: usize = 20;
+
+    // Flatten the tree into (label, node) rows first so the columns can be lined up
+    fn flatten<'a>(node: &'a DirNode, prefix: &str, rows: &mut Vec<(String, &'a DirNode)>) {
+        let children = node.children();
+        let count = children.len();
+        for (i, child) in children.into_iter().enumerate() {
+            let last = i == count - 1;
+            rows.push((
+                format!(
+                    "{}{}{}",
+                    prefix,
+                    if last { "└── " } else { "├── " },
+                    child.name
+                ),
+                child,
+            ));
+            flatten(
+                child,
+                &format!("{}{}", prefix, if last { "    " } else { "│   " }),
+                rows,
+            );
+        }
+    }
+    let mut rows = vec![(tree.name.clone(), &tree)];
+    flatten(&tree, "", &mut rows);
+
+    let label_col_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap();
+    let code_col_width = column_width(rows.iter().map(|(_, node)| node.code()), "");
+    let lang_col_width = rows
+        .iter()
+        .filter_map(|(_, node)| {
+            node.languages()
+                .first()
+                .map(|(lang, _)| lang.to_string().len())
+        })
+        .max()
+        .unwrap_or(0);
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for (label, node) in rows {
+        let code = node.code();
+        let languages = node.languages();
+        let Some((dominant, _)) = languages.first() else {
+            continue;
+        };
+
+        // Pad by characters since the box drawing characters are multiple bytes
+        let padding = label_col_width - label.chars().count();
+        print!(
+            "{}{}  {:>code_col_width$}  ",
+            label,
+            " ".repeat(padding),
+            code
+        );
+
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(Some(dominant.color())))
+            .unwrap();
+        print!("{:<lang_col_width$}", dominant.to_string());
+        print!("  ");
+
+        let segments = languages
+            .iter()
+            .map(|(lang, stats)| (*lang, stats.code))
+            .collect::<Vec<_>>();
+        print_stacked_bar(&mut stdstream_stdout, &segments, code, MINI_BAR_WIDTH);
+        println!();
+    }
+}
+
+pub fn print_results_csv(results: Vec<(Language, LineStats)>) {
+    let total_code = results.iter().map(|r| r.1.code).sum::<usize>();
+
+    println!("language,code,comments,blanks,total,percent,color");
+    for (lang, stats) in results {
+        println!(
+            "{},{},{},{},{},{:.2},{}",
+            csv_field(&lang.to_string()),
+            stats.code,
+            stats.comments,
+            stats.blanks,
+            stats.total,
+            percent(stats.code, total_code),
+            color_hex(lang.color())
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct JsonHistory {
+    root: String,
+    history: Vec<JsonHistoryPoint>,
+}
+
+#[derive(Serialize)]
+struct JsonHistoryPoint {
+    revision: String,
+    date: String,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    total: usize,
+    languages: Vec<JsonLanguage>,
+}
+
+pub fn print_history_json(history: Vec<HistoryPoint>, pwd: &Path) {
+    let report = JsonHistory {
+        root: pwd.to_string_lossy().into_owned(),
+        history: history
+            .into_iter()
+            .map(|point| {
+                let mut totals = LineStats::default();
+                for (_, stats) in point.languages.iter() {
+                    totals += *stats;
+                }
+
+                JsonHistoryPoint {
+                    revision: point.commit.revision,
+                    date: point.commit.date,
+                    code: totals.code,
+                    comments: totals.comments,
+                    blanks: totals.blanks,
+                    total: totals.total,
+                    languages: json_languages(point.languages, totals.code),
+                }
+            })
+            .collect(),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+pub fn print_history_csv(history: Vec<HistoryPoint>) {
+    println!("revision,date,language,code,comments,blanks,total,percent,color");
+    for point in history {
+        let total_code = point.languages.iter().map(|r| r.1.code).sum::<usize>();
+        for (lang, stats) in point.languages {
+            println!(
+                "{},{},{},{},{},{},{},{:.2},{}",
+                csv_field(&point.commit.revision),
+                point.commit.date,
+                csv_field(&lang.to_string()),
+                stats.code,
+                stats.comments,
+                stats.blanks,
+                stats.total,
+                percent(stats.code, total_code),
+                color_hex(lang.color())
+            );
+        }
+    }
+}
+
+/// Prints a stacked bar per revision, scaled to the revision with the most code
+pub fn print_history(
+    history: Vec<HistoryPoint>,
+    pwd: &Path,
+    max_width: Option<u16>,
+    color: ColorChoice,
+) {
+    if history.is_empty() {
+        return;
+    }
+
+    println!("Language history for {:?}:\n", pwd);
+
+    // Languages are stacked in the same order in every bar, largest in the latest revision first
+    let mut languages = history
+        .last()
+        .unwrap()
+        .languages
+        .iter()
+        .map(|(lang, _)| *lang)
+        .collect::<Vec<_>>();
+    for point in history.iter().rev() {
+        for (lang, _) in point.languages.iter() {
+            if !languages.contains(lang) {
+                languages.push(*lang);
+            }
+        }
+    }
+
+    let totals = history
+        .iter()
+        .map(|point| point.languages.iter().map(|(_, s)| s.code).sum::<usize>())
+        .collect::<Vec<_>>();
+    let max_total = totals.iter().copied().max().unwrap().max(1);
+
+    let revision_col_width = history
+        .iter()
+        .map(|point| point.commit.revision.chars().count())
+        .max()
+        .unwrap();
+    let date_col_width = history
+        .iter()
+        .map(|point| point.commit.date.len())
+        .max()
+        .unwrap();
+    let total_col_width = column_width(totals.iter().copied(), "");
+
+    let columns = terminal_columns(max_width) as usize;
+    let bar_col_width = columns
+        .saturating_sub(date_col_width + revision_col_width + total_col_width + 6) // 3 paddings
+        .max(1);
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for (point, total) in history.iter().zip(totals) {
+        print!(
+            "{:<date_col_width$}  {:<revision_col_width$}  ",
+            point.commit.date, point.commit.revision
+        );
+
+        let segments = languages
+            .iter()
+            .filter_map(|lang| {
+                let (_, stats) = point.languages.iter().find(|(l, _)| l == lang)?;
+                Some((*lang, stats.code))
+            })
+            .collect::<Vec<_>>();
+        let bar_width =
+            print_stacked_bar(&mut stdstream_stdout, &segments, max_total, bar_col_width);
+
+        println!(
+            "{}  {:>total_col_width$}",
+            " ".repeat(bar_col_width - bar_width),
+            total
+        );
+    }
+
+    println!();
+    print_legend(
+        &mut stdstream_stdout,
+        languages
+            .into_iter()
+            .map(|lang| (lang, lang.to_string()))
+            .collect(),
+        columns,
+    );
+}
+
+/// Prints the languages as one bar, each taking up its share of `total` out of `width` columns.
+/// Returns the number of columns printed.
+fn print_stacked_bar(
+    stdstream_stdout: &mut StandardStream,
+    segments: &[(Language, usize)],
+    total: usize,
+    width: usize,
+) -> usize {
+    // Rounding the running total keeps the rounding errors from adding up along the bar
+    let mut stacked = 0;
+    let mut bar_width = 0;
+    for (lang, lines) in segments {
+        stacked += lines;
+        let end = (stacked as f32 / total.max(1) as f32 * width as f32).round() as usize;
+
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(Some(lang.color())))
+            .unwrap();
+        print!("{}", "█".repeat(end - bar_width));
+        bar_width = end;
+    }
+    stdstream_stdout.reset().unwrap();
+
+    bar_width
+}
+
+/// Prints a colored swatch and label for each language, wrapping at `columns`
+fn print_legend(
+    stdstream_stdout: &mut StandardStream,
+    entries: Vec<(Language, String)>,
+    columns: usize,
+) {
+    let mut line_width = 0;
+    for (lang, label) in entries {
+        let entry_width = label.chars().count() + 2; // swatch and space
+        if line_width > 0 && line_width + 2 + entry_width > columns {
+            println!();
+            line_width = 0;
+        } else if line_width > 0 {
+            print!("  ");
+            line_width += 2;
+        }
+
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(Some(lang.color())))
+            .unwrap();
+        print!("█");
+        stdstream_stdout.reset().unwrap();
+        print!(" {}", label);
+        line_width += entry_width;
+    }
+    println!();
+}
+
+#[derive(Serialize)]
+struct JsonDiff {
+    before: String,
+    after: String,
+    languages: Vec<JsonLanguageDiff>,
+}
+
+#[derive(Serialize)]
+struct JsonLanguageDiff {
+    name: String,
+    before: usize,
+    after: usize,
+    growth: usize,
+    shrinkage: usize,
+    net: isize,
+    before_percent: f64,
+    after_percent: f64,
+    shift: f64,
+    color: String,
+}
+
+pub fn print_diff_json(diffs: Vec<LanguageDiff>, before: &str, after: &str) {
+    let before_total = diffs.iter().map(|d| d.before).sum::<usize>();
+    let after_total = diffs.iter().map(|d| d.after).sum::<usize>();
+
+    let report = JsonDiff {
+        before: before.to_string(),
+        after: after.to_string(),
+        languages: diffs
+            .into_iter()
+            .map(|diff| {
+                let before_percent = percent(diff.before, before_total);
+                let after_percent = percent(diff.after, after_total);
+                JsonLanguageDiff {
+                    name: diff.lang.to_string(),
+                    before: diff.before,
+                    after: diff.after,
+                    growth: diff.growth,
+                    shrinkage: diff.shrinkage,
+                    net: diff.net(),
+                    before_percent,
+                    after_percent,
+                    shift: after_percent - before_percent,
+                    color: color_hex(diff.lang.color()),
+                }
+            })
+            .collect(),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+pub fn print_diff_csv(diffs: Vec<LanguageDiff>) {
+    let before_total = diffs.iter().map(|d| d.before).sum::<usize>();
+    let after_total = diffs.iter().map(|d| d.after).sum::<usize>();
 
-pub fn print_results_summary(results: Vec<(Language, usize)>, length: usize) {
+    println!("language,before,after,growth,shrinkage,net,before_percent,after_percent,shift,color");
+    for diff in diffs {
+        let before_percent = percent(diff.before, before_total);
+        let after_percent = percent(diff.after, after_total);
+        println!(
+            "{},{},{},{},{},{},{:.2},{:.2},{:.2},{}",
+            csv_field(&diff.lang.to_string()),
+            diff.before,
+            diff.after,
+            diff.growth,
+            diff.shrinkage,
+            diff.net(),
+            before_percent,
+            after_percent,
+            after_percent - before_percent,
+            color_hex(diff.lang.color())
+        );
+    }
+}
+
+/// Formats a change in line count with its sign, no change is a plain 0
+fn signed(value: isize) -> String {
+    match value {
+        0 => String::from("0"),
+        _ => format!("{:+}", value),
+    }
+}
+
+/// Prints the change in code per language, growth in green and shrinkage in red
+pub fn print_diff(diffs: Vec<LanguageDiff>, before: &str, after: &str, color: ColorChoice) {
+    if diffs.is_empty() {
+        return;
+    }
+
+    println!("Language changes from {:?} to {:?}:\n", before, after);
+
+    const LANG_HEADER: &str = "Language";
+    const BEFORE_HEADER: &str = "Before";
+    const AFTER_HEADER: &str = "After";
+    const GROWTH_HEADER: &str = "Growth";
+    const SHRINKAGE_HEADER: &str = "Shrinkage";
+    const NET_HEADER: &str = "Net";
+    const SHIFT_HEADER: &str = "Shift";
+
+    let before_total = diffs.iter().map(|d| d.before).sum::<usize>();
+    let after_total = diffs.iter().map(|d| d.after).sum::<usize>();
+
+    let shifts = diffs
+        .iter()
+        .map(|d| percent(d.after, after_total) - percent(d.before, before_total))
+        .collect::<Vec<_>>();
+    let rows = diffs
+        .iter()
+        .zip(shifts.iter())
+        .map(|(diff, shift)| {
+            [
+                signed(diff.growth as isize),
+                signed(-(diff.shrinkage as isize)),
+                signed(diff.net()),
+                format!("{:+.1}%", shift),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let text_width = |column: usize, header: &str| {
+        rows.iter()
+            .map(|row| row[column].len())
+            .max()
+            .unwrap()
+            .max(header.len())
+    };
+
+    let lang_col_width = diffs
+        .iter()
+        .map(|d| d.lang.to_string().len())
+        .max()
+        .unwrap()
+        .max(LANG_HEADER.len());
+    let before_col_width = column_width(diffs.iter().map(|d| d.before), BEFORE_HEADER);
+    let after_col_width = column_width(diffs.iter().map(|d| d.after), AFTER_HEADER);
+    let growth_col_width = text_width(0, GROWTH_HEADER);
+    let shrinkage_col_width = text_width(1, SHRINKAGE_HEADER);
+    let net_col_width = text_width(2, NET_HEADER);
+    let shift_col_width = text_width(3, SHIFT_HEADER);
+
+    println!(
+        "{:<lang_col_width$}  {:>before_col_width$}  {:>after_col_width$}  {:>growth_col_width$}  {:>shrinkage_col_width$}  {:>net_col_width$}  {:>shift_col_width$}",
+        LANG_HEADER,
+        BEFORE_HEADER,
+        AFTER_HEADER,
+        GROWTH_HEADER,
+        SHRINKAGE_HEADER,
+        NET_HEADER,
+        SHIFT_HEADER
+    );
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    let mut print_signed = |text: &str, width: usize, sign: f64| {
+        let color = if sign > 0.0 {
+            Some(Color::Green)
+        } else if sign < 0.0 {
+            Some(Color::Red)
+        } else {
+            None
+        };
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(color))
+            .unwrap();
+        print!("  {:>width$}", text);
+        stdstream_stdout.reset().unwrap();
+    };
+
+    for ((diff, shift), [growth, shrinkage, net, shift_text]) in diffs.iter().zip(shifts).zip(rows)
+    {
+        print!(
+            "{:<lang_col_width$}  {:>before_col_width$}  {:>after_col_width$}",
+            diff.lang.to_string(),
+            diff.before,
+            diff.after
+        );
+        print_signed(&growth, growth_col_width, diff.growth as f64);
+        print_signed(&shrinkage, shrinkage_col_width, -(diff.shrinkage as f64));
+        print_signed(&net, net_col_width, diff.net() as f64);
+        // Keep shifts that round to zero uncolored
+        print_signed(&shift_text, shift_col_width, (shift * 10.0).round());
+        println!();
+    }
+}
+
+fn percent(lines: usize, total_lines: usize) -> f64 {
+    if total_lines == 0 {
+        0.0
+    } else {
+        lines as f64 / total_lines as f64 * 100.0
+    }
+}
+
+#[derive(Serialize)]
+struct JsonFileReport {
+    root: String,
+    files: Vec<JsonFile>,
+}
+
+#[derive(Serialize)]
+struct JsonFile {
+    path: String,
+    language: String,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    total: usize,
+}
+
+pub fn print_file_report_json(files: Vec<FileReport>, pwd: &Path) {
+    let report = JsonFileReport {
+        root: pwd.to_string_lossy().into_owned(),
+        files: files
+            .into_iter()
+            .map(|file| JsonFile {
+                path: relative_path(&file, pwd),
+                language: file.lang.to_string(),
+                code: file.stats.code,
+                comments: file.stats.comments,
+                blanks: file.stats.blanks,
+                total: file.stats.total,
+            })
+            .collect(),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Unable to serialize results")
+    );
+}
+
+pub fn print_file_report_csv(files: Vec<FileReport>, pwd: &Path) {
+    println!("path,language,code,comments,blanks,total");
+    for file in files {
+        println!(
+            "{},{},{},{},{},{}",
+            csv_field(&relative_path(&file, pwd)),
+            csv_field(&file.lang.to_string()),
+            file.stats.code,
+            file.stats.comments,
+            file.stats.blanks,
+            file.stats.total
+        );
+    }
+}
+
+pub fn print_file_report(files: Vec<FileReport>, pwd: &Path, color: ColorChoice) {
+    if files.is_empty() {
+        return;
+    }
+
+    println!("File breakdown for {:?}:\n", pwd);
+
+    const PATH_HEADER: &str = "Path";
+    const LANG_HEADER: &str = "Language";
+    const CODE_HEADER: &str = "Code";
+    const COMMENTS_HEADER: &str = "Comments";
+    const BLANKS_HEADER: &str = "Blanks";
+
+    let paths = files
+        .iter()
+        .map(|file| relative_path(file, pwd))
+        .collect::<Vec<_>>();
+
+    let path_col_width = paths
+        .iter()
+        .map(|path| path.chars().count())
+        .max()
+        .unwrap()
+        .max(PATH_HEADER.len());
+    let lang_col_width = files
+        .iter()
+        .map(|file| file.lang.to_string().len())
+        .max()
+        .unwrap()
+        .max(LANG_HEADER.len());
+    let code_col_width = column_width(files.iter().map(|f| f.stats.code), CODE_HEADER);
+    let comments_col_width = column_width(files.iter().map(|f| f.stats.comments), COMMENTS_HEADER);
+    let blanks_col_width = column_width(files.iter().map(|f| f.stats.blanks), BLANKS_HEADER);
+
+    println!(
+        "{:<path_col_width$}  {:<lang_col_width$}  {:>code_col_width$}  {:>comments_col_width$}  {:>blanks_col_width$}",
+        PATH_HEADER, LANG_HEADER, CODE_HEADER, COMMENTS_HEADER, BLANKS_HEADER
+    );
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for (file, path) in files.into_iter().zip(paths) {
+        print!("{:<path_col_width$}  ", path);
+
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(Some(file.lang.color())))
+            .unwrap();
+        print!("{:<lang_col_width$}", file.lang.to_string());
+        stdstream_stdout.reset().unwrap();
+
+        println!(
+            "  {:>code_col_width$}  {:>comments_col_width$}  {:>blanks_col_width$}",
+            file.stats.code, file.stats.comments, file.stats.blanks
+        );
+    }
+}
+
+pub fn print_skipped(skipped: &[SkippedFile], pwd: &Path) {
+    if skipped.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "Skipped {} {}:",
+        skipped.len(),
+        if skipped.len() == 1 { "file" } else { "files" }
+    );
+    for file in skipped.iter().sorted_by(|a, b| a.path.cmp(&b.path)) {
+        eprintln!(
+            "  {}: {}",
+            file.path
+                .strip_prefix(pwd)
+                .unwrap_or(&file.path)
+                .to_string_lossy(),
+            file.reason
+        );
+    }
+}
+
+fn column_width(values: impl Iterator<Item = usize>, header: &str) -> usize {
+    values
+        .map(|value| value.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max(header.len())
+}
+
+fn relative_path(file: &FileReport, pwd: &Path) -> String {
+    relative_to(&file.path, pwd)
+}
+
+fn relative_to(path: &Path, pwd: &Path) -> String {
+    match path.strip_prefix(pwd) {
+        Ok(path) if path.as_os_str().is_empty() => String::from("."),
+        Ok(path) => path.to_string_lossy().into_owned(),
+        Err(_) => path.to_string_lossy().into_owned(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn print_results_summary(results: Vec<(Language, usize)>, length: usize, color: ColorChoice) {
     let total_lines = results.iter().map(|r| r.1).sum::<usize>();
-    let mut stdstream_stdout = StandardStream::stdout(ColorChoice::Always);
+    let mut stdstream_stdout = StandardStream::stdout(color);
 
     let length = length.min(results.len());
     let truncated = length < results.len();
@@ -38,45 +1067,161 @@ pub fn print_results_summary(results: Vec<(Language, usize)>, length: usize) {
     }
 }
 
-pub fn print_results_compact(results: Vec<(Language, usize)>, max_width: Option<u16>) {
+/// Prints every language as part of a single bar, with a legend of their percentages below it
+pub fn print_results_bar(
+    results: Vec<(Language, usize)>,
+    max_width: Option<u16>,
+    color: ColorChoice,
+) {
     if results.is_empty() {
         return;
     }
 
     let total_lines = results.iter().map(|r| r.1).sum::<usize>();
-    let window_size = crossterm::terminal::window_size().expect("Couldn't get terminal size");
-    let columns = match max_width {
-        Some(max_width) => max_width.min(window_size.columns),
-        None => window_size.columns,
+    let columns = terminal_columns(max_width) as usize;
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    print_stacked_bar(&mut stdstream_stdout, &results, total_lines, columns);
+    println!();
+    print_legend(
+        &mut stdstream_stdout,
+        results
+            .into_iter()
+            .map(|(lang, lines)| {
+                let percent = percent(lines, total_lines);
+                // Keep a digit for the languages that would otherwise round to nothing
+                let label = if percent < 0.1 {
+                    format!("{} <0.1%", lang)
+                } else {
+                    format!("{} {:.1}%", lang, percent)
+                };
+                (lang, label)
+            })
+            .collect(),
+        columns,
+    );
+}
+
+/// Prints the project's initials next to facts about it, in the style of neofetch
+pub fn print_fetch(fetch: Fetch, color: ColorChoice) {
+    const FETCH_LANGUAGES: usize = 5;
+    const FETCH_BAR_WIDTH: usize = 12;
+
+    let accent = fetch
+        .languages
+        .first()
+        .map_or(Color::Rgb(128, 128, 128), |(lang, _)| lang.color());
+
+    // Each fact is a list of (color, text) parts
+    let mut facts: Vec<Vec<(Option<Color>, String)>> = vec![
+        vec![(Some(accent), fetch.name.clone())],
+        vec![(None, "-".repeat(fetch.name.chars().count()))],
+    ];
+    let mut fact = |key: &str, value: String| {
+        facts.push(vec![(Some(accent), format!("{}: ", key)), (None, value)]);
     };
+    fact("Path", fetch.root.clone());
+    fact("Files", fetch.files.to_string());
+    fact(
+        "Lines",
+        format!(
+            "{} code, {} comments, {} blanks",
+            fetch.totals.code, fetch.totals.comments, fetch.totals.blanks
+        ),
+    );
+    if let Some(branch) = fetch.branch.clone() {
+        fact("Branch", branch);
+    }
+    if let Some(last_commit) = fetch.last_commit.clone() {
+        fact("Last commit", last_commit);
+    }
 
-    let mut current_width = 0;
+    if !fetch.languages.is_empty() {
+        facts.push(vec![(Some(accent), String::from("Languages:"))]);
+    }
+    let languages = fetch
+        .languages
+        .iter()
+        .take(FETCH_LANGUAGES)
+        .collect::<Vec<_>>();
+    let lang_col_width = languages
+        .iter()
+        .map(|(lang, _)| lang.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (lang, stats) in languages {
+        let percent = percent(stats.code, fetch.totals.code);
+        let eighths = (percent / 100.0 * FETCH_BAR_WIDTH as f64 * 8.0).round() as usize;
+        facts.push(vec![
+            (None, format!("  {:<lang_col_width$} ", lang.to_string())),
+            (
+                Some(lang.color()),
+                format!("{:<FETCH_BAR_WIDTH$}", bar(eighths.max(1))),
+            ),
+            (None, format!(" {:>5.1}%", percent)),
+        ]);
+    }
 
-    let mut stdstream_stdout = StandardStream::stdout(ColorChoice::Always);
-    for (lang, lines) in results {
-        let result_width = ((lines as f32 / total_lines as f32) * columns as f32).round() as u16;
-        if result_width <= 1 {
-            // Blocks are too small now, just print "Other"
-            let other_bg = Color::Rgb(128, 128, 128);
-            let fg = foreground_color(other_bg);
+    let art = fetch.art();
+    let art_width = art.iter().map(|row| row.chars().count()).max().unwrap_or(0);
 
-            let name = "Other";
-            let block = (0..(columns - current_width) as usize)
-                .map(|i| name.chars().nth(i).unwrap_or(' '))
-                .collect::<String>();
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for i in 0..art.len().max(facts.len()) {
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_fg(Some(accent)).set_bold(true))
+            .unwrap();
+        let row = art.get(i).map_or("", |row| row.as_str());
+        print!("{}{}   ", row, " ".repeat(art_width - row.chars().count()));
+        stdstream_stdout.reset().unwrap();
 
+        for (color, text) in facts.get(i).into_iter().flatten() {
             stdstream_stdout
-                .set_color(ColorSpec::new().set_bg(Some(other_bg)).set_fg(Some(fg)))
+                .set_color(ColorSpec::new().set_fg(*color).set_bold(color.is_some()))
                 .unwrap();
-            print!("{}", block);
-
-            break;
+            print!("{}", text);
+            stdstream_stdout.reset().unwrap();
         }
+        println!();
+    }
+}
+
+pub fn print_results_compact(
+    results: Vec<(Language, usize)>,
+    max_width: Option<u16>,
+    color: ColorChoice,
+) {
+    if results.is_empty() {
+        return;
+    }
+
+    let total_lines = results.iter().map(|r| r.1).sum::<usize>();
+    let columns = terminal_columns(max_width);
+
+    // Blocks are too small to show a name past this, the rest is grouped as "Other"
+    let other_bg = Color::Rgb(128, 128, 128);
+    let shown = results
+        .iter()
+        .take_while(|(_, lines)| {
+            ((*lines as f32 / total_lines as f32) * columns as f32).round() as u16 > 1
+        })
+        .count();
+
+    // Segment boundaries are tracked in eighths of a column, a boundary inside a column is drawn
+    // with a partial block in the color of the segment on its left over that of the one on its
+    // right
+    let eighths = columns as usize * 8;
+    let mut stacked = 0;
+    let mut start: usize = 0;
+
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for (i, (lang, lines)) in results.iter().take(shown).enumerate() {
+        stacked += lines;
+        let end = (stacked as f32 / total_lines as f32 * eighths as f32).round() as usize;
 
         let fg = foreground_color(lang.color());
 
         let name = lang.to_string();
-        let block = (0..result_width as usize)
+        let block = (0..(end / 8).saturating_sub(start.div_ceil(8)))
             .map(|i| name.chars().nth(i).unwrap_or(' '))
             .collect::<String>();
 
@@ -90,79 +1235,160 @@ pub fn print_results_compact(results: Vec<(Language, usize)>, max_width: Option<
             .unwrap();
         print!("{}", block);
 
-        current_width += result_width;
+        if let Some(partial) = partial_block(end % 8) {
+            let next_bg = match results.get(i + 1) {
+                Some((next, _)) if i + 1 < shown => next.color(),
+                _ => other_bg,
+            };
+            stdstream_stdout
+                .set_color(
+                    ColorSpec::new()
+                        .set_bg(Some(next_bg))
+                        .set_fg(Some(lang.color())),
+                )
+                .unwrap();
+            print!("{}", partial);
+        }
+
+        start = end;
+    }
+
+    if shown < results.len() {
+        let fg = foreground_color(other_bg);
+
+        let name = "Other";
+        let block = (0..(columns as usize).saturating_sub(start.div_ceil(8)))
+            .map(|i| name.chars().nth(i).unwrap_or(' '))
+            .collect::<String>();
+
+        stdstream_stdout
+            .set_color(ColorSpec::new().set_bg(Some(other_bg)).set_fg(Some(fg)))
+            .unwrap();
+        print!("{}", block);
     }
     stdstream_stdout.reset().unwrap();
     println!();
 }
 
-pub fn print_results(results: Vec<(Language, usize)>, pwd: &Path, max_width: Option<u16>) {
+/// Left-aligned block glyph covering `eighths` eighths of a column, if it isn't empty or full
+fn partial_block(eighths: usize) -> Option<char> {
+    match eighths {
+        1 => Some('▏'),
+        2 => Some('▎'),
+        3 => Some('▍'),
+        4 => Some('▌'),
+        5 => Some('▋'),
+        6 => Some('▊'),
+        7 => Some('▉'),
+        _ => None,
+    }
+}
+
+/// Full blocks followed by a partial block for a bar `eighths` eighths of a column long
+fn bar(eighths: usize) -> String {
+    let mut bar = "█".repeat(eighths / 8);
+    bar.extend(partial_block(eighths % 8));
+
+    bar
+}
+
+pub fn print_results(
+    results: Vec<(Language, LineStats)>,
+    title: &str,
+    max_width: Option<u16>,
+    color: ColorChoice,
+) {
     if results.is_empty() {
         return;
     }
 
-    println!("Language distribution for {:?}:\n", pwd);
+    println!("{}:\n", title);
 
     const LANG_HEADER: &str = "Language";
     const LINES_HEADER: &str = "# Lines";
+    const COMMENTS_HEADER: &str = "Comments";
+    const BLANKS_HEADER: &str = "Blanks";
 
-    let max_lines = results.first().unwrap().1;
-    let lines_col_width = results
-        .iter()
-        .map(|(_, size)| size.human_count_bare().to_string().len())
-        .max()
-        .unwrap()
-        .max(LANG_HEADER.len()) as u16;
+    let max_lines = results.first().unwrap().1.code;
+    let lines_col_width = column_width(results.iter().map(|(_, s)| s.code), LINES_HEADER) as u16;
+    let comments_col_width =
+        column_width(results.iter().map(|(_, s)| s.comments), COMMENTS_HEADER) as u16;
+    let blanks_col_width =
+        column_width(results.iter().map(|(_, s)| s.blanks), BLANKS_HEADER) as u16;
     let lang_col_width = results
         .iter()
         .map(|(lang, _)| lang.to_string().len())
         .max()
         .unwrap()
-        .max(LINES_HEADER.len()) as u16;
+        .max(LANG_HEADER.len()) as u16;
 
-    let window_size = crossterm::terminal::window_size().expect("Couldn't get terminal size");
-    let columns = match max_width {
-        Some(max_width) => max_width.min(window_size.columns),
-        None => window_size.columns,
-    };
+    let columns = terminal_columns(max_width);
 
-    let bar_col_width = columns - lines_col_width - lang_col_width - 2; // -2 for
-    // padding
+    let blanks_col_start = columns.saturating_sub(blanks_col_width);
+    let comments_col_start = blanks_col_start.saturating_sub(comments_col_width + 1); // +1 for padding
+    let lines_col_start = comments_col_start.saturating_sub(lines_col_width + 1); // +1 for padding
     let bar_col_start = lang_col_width + 1; // +1 for padding
-    let lines_col_start = columns - lines_col_width + 1; // +1 for padding
+    let bar_col_width = lines_col_start.saturating_sub(bar_col_start + 1).max(1); // +1 for padding
+    // Too narrow terminals push the counts past the edge rather than dropping the bar
+    let lines_col_start = lines_col_start.max(bar_col_start + bar_col_width + 1);
 
-    print!("{}", LANG_HEADER);
-    std::io::stdout()
-        .execute(MoveToColumn(lines_col_start))
-        .unwrap();
-    println!("{}", LINES_HEADER);
+    // Columns are padded with spaces rather than moving the cursor, so the table can be
+    // redirected to a file
+    let lines_col_width = lines_col_width as usize;
+    let comments_col_width = comments_col_width as usize;
+    let blanks_col_width = blanks_col_width as usize;
+    println!(
+        "{:<lines_col_start$}{:>lines_col_width$} {:>comments_col_width$} {:>blanks_col_width$}",
+        LANG_HEADER,
+        LINES_HEADER,
+        COMMENTS_HEADER,
+        BLANKS_HEADER,
+        lines_col_start = lines_col_start as usize
+    );
 
-    let mut stdstream_stdout = StandardStream::stdout(ColorChoice::Always);
-    for (lang, lines) in results {
-        print!("{}", lang);
-        std::io::stdout()
-            .execute(MoveToColumn(bar_col_start))
-            .unwrap();
+    let mut stdstream_stdout = StandardStream::stdout(color);
+    for (lang, stats) in results {
+        print!(
+            "{:<bar_col_start$}",
+            lang.to_string(),
+            bar_col_start = bar_col_start as usize
+        );
 
+        let bar = bar(
+            ((stats.code as f32 / max_lines as f32) * bar_col_width as f32 * 8.)
+                .round()
+                .max(1.) as usize,
+        );
+        let bar_padding = (bar_col_width as usize + 1).saturating_sub(bar.chars().count());
         stdstream_stdout
             .set_color(ColorSpec::new().set_fg(Some(lang.color())))
             .unwrap();
-        print!(
-            "{}",
-            vec![
-                '█';
-                ((lines as f32 / max_lines as f32) * bar_col_width as f32)
-                    .round()
-                    .max(1.) as usize
-            ]
-            .into_iter()
-            .collect::<String>()
-        );
+        print!("{}", bar);
         stdstream_stdout.reset().unwrap();
-        std::io::stdout()
-            .execute(MoveToColumn(lines_col_start))
-            .unwrap();
-        println!("{}", lines);
+
+        println!(
+            "{}{:>lines_col_width$} {:>comments_col_width$} {:>blanks_col_width$}",
+            " ".repeat(bar_padding),
+            stats.code,
+            stats.comments,
+            stats.blanks
+        );
+    }
+}
+
+/// Width of the terminal, limited to `max_width`. Falls back to 80 columns when it can't be
+/// determined, e.g. when the output is redirected.
+fn terminal_columns(max_width: Option<u16>) -> u16 {
+    const DEFAULT_COLUMNS: u16 = 80;
+
+    let columns = match crossterm::terminal::window_size() {
+        Ok(size) if size.columns > 0 => size.columns,
+        _ => DEFAULT_COLUMNS,
+    };
+
+    match max_width {
+        Some(max_width) => max_width.min(columns),
+        None => columns,
     }
 }
 
@@ -189,6 +1415,14 @@ fn foreground_color(background: Color) -> Color {
     }
 }
 
+fn color_hex(color: Color) -> String {
+    let Color::Rgb(r, g, b) = color else {
+        panic!("Cannot format non-RGB color as hex");
+    };
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
 fn contrast_ratio(l1: f32, l2: f32) -> f32 {
     if l1 > l2 {
         (l1 + 0.05) / (l2 + 0.05)
@@ -212,4 +1446,62 @@ mod tests {
 
         assert_eq!(actual, fg);
     }
+
+    #[test_case(12, "+12")]
+    #[test_case(-3, "-3")]
+    #[test_case(0, "0")]
+    fn test_signed(value: isize, expected: &str) {
+        let actual = signed(value);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("Rust", "Rust")]
+    #[test_case("src/a,b.rs", "\"src/a,b.rs\"")]
+    #[test_case("say \"hi\".sh", "\"say \"\"hi\"\".sh\"")]
+    fn test_csv_field(field: &str, expected: &str) {
+        let actual = csv_field(field);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case(ColorWhen::Always, false, false, false, ColorChoice::Always)]
+    #[test_case(ColorWhen::Always, true, false, true, ColorChoice::Always)]
+    #[test_case(ColorWhen::Never, false, true, true, ColorChoice::Never)]
+    #[test_case(ColorWhen::Auto, false, false, true, ColorChoice::Auto)]
+    #[test_case(ColorWhen::Auto, false, false, false, ColorChoice::Never)]
+    #[test_case(ColorWhen::Auto, true, false, true, ColorChoice::Never)]
+    #[test_case(ColorWhen::Auto, false, true, false, ColorChoice::Always)]
+    #[test_case(ColorWhen::Auto, true, true, false, ColorChoice::Never)]
+    fn test_resolve_color_choice(
+        when: ColorWhen,
+        no_color: bool,
+        clicolor_force: bool,
+        is_terminal: bool,
+        expected: ColorChoice,
+    ) {
+        let actual = resolve_color_choice(when, no_color, clicolor_force, is_terminal);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case(0, "")]
+    #[test_case(1, "▏")]
+    #[test_case(8, "█")]
+    #[test_case(12, "█▌")]
+    #[test_case(23, "██▉")]
+    fn test_bar(eighths: usize, expected: &str) {
+        let actual = bar(eighths);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case(Color::Rgb(0, 0, 0), "#000000")]
+    #[test_case(Color::Rgb(255, 67, 0), "#ff4300")]
+    #[test_case(Color::Rgb(0, 89, 156), "#00599c")]
+    fn test_color_hex(color: Color, expected: &str) {
+        let actual = color_hex(color);
+
+        assert_eq!(actual, expected);
+    }
 }
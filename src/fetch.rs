@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use crate::{comments::LineStats, git, lang::Language};
+
+/// Facts shown next to the art in the fetch layout
+pub struct Fetch {
+    pub name: String,
+    pub root: String,
+    pub files: usize,
+    pub totals: LineStats,
+    /// Sorted from most to least code
+    pub languages: Vec<(Language, LineStats)>,
+    pub branch: Option<String>,
+    pub last_commit: Option<String>,
+}
+
+impl Fetch {
+    pub fn new(root: &Path, files: usize, languages: Vec<(Language, LineStats)>) -> Fetch {
+        let mut totals = LineStats::default();
+        for (_, stats) in languages.iter() {
+            totals += *stats;
+        }
+
+        Fetch {
+            name: root
+                .file_name()
+                .map_or_else(|| root.to_string_lossy(), |name| name.to_string_lossy())
+                .into_owned(),
+            root: root.to_string_lossy().into_owned(),
+            files,
+            totals,
+            languages,
+            branch: git::branch(root),
+            last_commit: git::last_commit_date(root),
+        }
+    }
+
+    /// The project's initials in block letters
+    pub fn art(&self) -> Vec<String> {
+        block_letters(&initials(&self.name))
+    }
+}
+
+/// First letter of each word in the name, up to three of them
+fn initials(name: &str) -> String {
+    let initials = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .take(3)
+        .collect::<String>()
+        .to_ascii_uppercase();
+
+    if initials.is_empty() {
+        String::from("?")
+    } else {
+        initials
+    }
+}
+
+const GLYPH_HEIGHT: usize = 5;
+
+fn glyph(c: char) -> [&'static str; GLYPH_HEIGHT] {
+    match c {
+        'A' => [" ### ", "#   #", "#####", "#   #", "#   #"],
+        'B' => ["#### ", "#   #", "#### ", "#   #", "#### "],
+        'C' => [" ####", "#    ", "#    ", "#    ", " ####"],
+        'D' => ["#### ", "#   #", "#   #", "#   #", "#### "],
+        'E' => ["#####", "#    ", "#### ", "#    ", "#####"],
+        'F' => ["#####", "#    ", "#### ", "#    ", "#    "],
+        'G' => [" ####", "#    ", "#  ##", "#   #", " ####"],
+        'H' => ["#   #", "#   #", "#####", "#   #", "#   #"],
+        'I' => ["#####", "  #  ", "  #  ", "  #  ", "#####"],
+        'J' => ["#####", "    #", "    #", "#   #", " ### "],
+        'K' => ["#   #", "#  # ", "###  ", "#  # ", "#   #"],
+        'L' => ["#    ", "#    ", "#    ", "#    ", "#####"],
+        'M' => ["#   #", "## ##", "# # #", "#   #", "#   #"],
+        'N' => ["#   #", "##  #", "# # #", "#  ##", "#   #"],
+        'O' => [" ### ", "#   #", "#   #", "#   #", " ### "],
+        'P' => ["#### ", "#   #", "#### ", "#    ", "#    "],
+        'Q' => [" ### ", "#   #", "# # #", "#  # ", " ## #"],
+        'R' => ["#### ", "#   #", "#### ", "#  # ", "#   #"],
+        'S' => [" ####", "#    ", " ### ", "    #", "#### "],
+        'T' => ["#####", "  #  ", "  #  ", "  #  ", "  #  "],
+        'U' => ["#   #", "#   #", "#   #", "#   #", " ### "],
+        'V' => ["#   #", "#   #", "#   #", " # # ", "  #  "],
+        'W' => ["#   #", "#   #", "# # #", "## ##", "#   #"],
+        'X' => ["#   #", " # # ", "  #  ", " # # ", "#   #"],
+        'Y' => ["#   #", " # # ", "  #  ", "  #  ", "  #  "],
+        'Z' => ["#####", "   # ", "  #  ", " #   ", "#####"],
+        '0' => [" ### ", "#  ##", "# # #", "##  #", " ### "],
+        '1' => ["  #  ", " ##  ", "  #  ", "  #  ", "#####"],
+        '2' => [" ### ", "#   #", "  ## ", " #   ", "#####"],
+        '3' => ["#### ", "    #", " ### ", "    #", "#### "],
+        '4' => ["#   #", "#   #", "#####", "    #", "    #"],
+        '5' => ["#####", "#    ", "#### ", "    #", "#### "],
+        '6' => [" ### ", "#    ", "#### ", "#   #", " ### "],
+        '7' => ["#####", "    #", "   # ", "  #  ", "  #  "],
+        '8' => [" ### ", "#   #", " ### ", "#   #", " ### "],
+        '9' => [" ### ", "#   #", " ####", "    #", " ### "],
+        _ => [" ### ", "#   #", "  ## ", "     ", "  #  "],
+    }
+}
+
+fn block_letters(text: &str) -> Vec<String> {
+    (0..GLYPH_HEIGHT)
+        .map(|row| {
+            text.chars()
+                .map(|c| glyph(c)[row].replace('#', "█"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("projfetch", "P")]
+    #[test_case("my-cool_project", "MCP")]
+    #[test_case("a.b.c.d", "ABC")]
+    #[test_case("2048-game", "2G")]
+    #[test_case("---", "?")]
+    fn test_initials(name: &str, expected: &str) {
+        let actual = initials(name);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_block_letters() {
+        let actual = block_letters("HI");
+
+        assert_eq!(
+            actual,
+            vec![
+                "█   █ █████",
+                "█   █   █  ",
+                "█████   █  ",
+                "█   █   █  ",
+                "█   █ █████",
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread::JoinHandle,
+};
+
+/// Lists the files under `path` that are tracked by git. Returns `None` when `path` isn't inside a
+/// git repository, or git isn't installed.
+pub fn tracked_files(path: &Path) -> Option<Vec<PathBuf>> {
+    let output = git(path, &["ls-files", "-z", "--cached"], None).ok()?;
+
+    Some(
+        output
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| path.join(String::from_utf8_lossy(name).as_ref()))
+            // Deleted files are still in the index, and submodules show up as directories
+            .filter(|path| path.is_file())
+            .collect(),
+    )
+}
+
+/// Reads the files under `path` as of the commit-ish `rev` from the object database, keeping only
+/// those whose path and size pass `filter`. Paths are joined onto `path`, like the working tree
+/// files.
+pub fn revision_files(
+    path: &Path,
+    rev: &str,
+    filter: impl Fn(&Path, u64) -> bool,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, String> {
+    // Without --full-tree, ls-tree only lists the part of the tree under the working directory
+    let output = git(path, &["ls-tree", "-r", "-l", "-z", rev], None)?;
+
+    let mut paths = Vec::new();
+    let mut objects = Vec::new();
+    for entry in output.split(|&b| b == 0).filter(|entry| !entry.is_empty()) {
+        // <mode> SP <type> SP <object> SP <padded size> TAB <file>
+        let entry = String::from_utf8_lossy(entry);
+        let Some((info, name)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut info = info.split_whitespace();
+        let (Some(mode), Some("blob"), Some(object), Some(size)) =
+            (info.next(), info.next(), info.next(), info.next())
+        else {
+            continue;
+        };
+        // Symlinks are stored as blobs containing the link target
+        if mode == "120000" {
+            continue;
+        }
+        let Ok(size) = size.parse::<u64>() else {
+            continue;
+        };
+
+        let file = path.join(name);
+        if filter(&file, size) {
+            paths.push(file);
+            objects.push(object.to_string());
+        }
+    }
+
+    let mut input = objects.join("\n");
+    input.push('\n');
+    let (mut child, writer) = spawn_git(path, &["cat-file", "--batch"], Some(input.into_bytes()))?;
+
+    // Each blob is printed as "<object> <type> <size>\n<contents>\n", read them one at a time
+    // rather than holding all of git's output at once
+    let unexpected_end = |_| String::from("Unexpected end of git cat-file output");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut blobs = Vec::with_capacity(paths.len());
+    let mut header = String::new();
+    for path in paths {
+        header.clear();
+        stdout.read_line(&mut header).map_err(unexpected_end)?;
+        let size = header
+            .trim_end()
+            .rsplit(' ')
+            .next()
+            .and_then(|size| size.parse::<usize>().ok())
+            .ok_or_else(|| format!("Unexpected git cat-file output \"{}\"", header.trim_end()))?;
+
+        let mut contents = vec![0; size];
+        stdout.read_exact(&mut contents).map_err(unexpected_end)?;
+        stdout.read_exact(&mut [0]).map_err(unexpected_end)?;
+        blobs.push((path, contents));
+    }
+    drop(stdout);
+
+    wait_git(child, writer)?;
+
+    Ok(blobs)
+}
+
+/// Runs git in `path`, returning its stdout or its error message on failure
+fn git(path: &Path, args: &[&str], input: Option<Vec<u8>>) -> Result<Vec<u8>, String> {
+    let (child, writer) = spawn_git(path, args, input)?;
+
+    wait_git(child, writer)
+}
+
+type InputWriter = JoinHandle<std::io::Result<()>>;
+
+/// Starts git in `path` with `input` written to its stdin
+fn spawn_git(
+    path: &Path,
+    args: &[&str],
+    input: Option<Vec<u8>>,
+) -> Result<(Child, InputWriter), String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    // Write on another thread so a full stdout pipe can't deadlock against a full stdin pipe
+    let stdin = child.stdin.take();
+    let writer = std::thread::spawn(move || {
+        if let (Some(mut stdin), Some(input)) = (stdin, input) {
+            stdin.write_all(&input)
+        } else {
+            Ok(())
+        }
+    });
+
+    Ok((child, writer))
+}
+
+/// Waits for git to exit, returning whatever of its stdout hasn't been read yet or its error
+/// message on failure
+fn wait_git(child: Child, writer: InputWriter) -> Result<Vec<u8>, String> {
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    writer
+        .join()
+        .unwrap()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(output.stdout)
+}
+
+pub struct Commit {
+    /// Commit-ish that can be passed back to git, an abbreviated hash or a tag name
+    pub revision: String,
+    /// Commit date, YYYY-MM-DD
+    pub date: String,
+}
+
+/// Lists the commits on the first-parent history of HEAD that touch `path`, newest first
+pub fn log(path: &Path) -> Result<Vec<Commit>, String> {
+    let output = git(
+        path,
+        &[
+            "log",
+            "--first-parent",
+            "--format=%h %cs",
+            "HEAD",
+            "--",
+            ".",
+        ],
+        None,
+    )?;
+
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| {
+            let (revision, date) = line.split_once(' ')?;
+            Some(Commit {
+                revision: revision.to_string(),
+                date: date.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Lists the repository's tags, oldest first
+pub fn tags(path: &Path) -> Result<Vec<Commit>, String> {
+    let output = git(
+        path,
+        &[
+            "for-each-ref",
+            "--sort=creatordate",
+            "--format=%(refname:short)%09%(creatordate:short)",
+            "refs/tags",
+        ],
+        None,
+    )?;
+
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| {
+            let (revision, date) = line.split_once('\t')?;
+            Some(Commit {
+                revision: revision.to_string(),
+                date: date.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// The checked out branch, or the abbreviated commit hash when HEAD is detached
+pub fn branch(path: &Path) -> Option<String> {
+    let output = git(path, &["symbolic-ref", "--short", "-q", "HEAD"], None)
+        .or_else(|_| git(path, &["rev-parse", "--short", "HEAD"], None))
+        .ok()?;
+
+    Some(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// Date of the last commit, YYYY-MM-DD
+pub fn last_commit_date(path: &Path) -> Option<String> {
+    let output = git(path, &["log", "-1", "--format=%cs"], None).ok()?;
+    let date = String::from_utf8_lossy(&output).trim().to_string();
+
+    (!date.is_empty()).then_some(date)
+}
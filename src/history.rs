@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::{
+    comments::LineStats,
+    git::{self, Commit},
+    lang::Language,
+    scan::{ScanOptions, is_candidate, scan_blobs},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum HistorySampling {
+    /// Every N commits on the first-parent history, see --every
+    Commits,
+    /// Every tag
+    Tags,
+    /// The last commit of every month
+    Monthly,
+}
+
+pub struct HistoryPoint {
+    pub commit: Commit,
+    /// Sorted from most to least code
+    pub languages: Vec<(Language, LineStats)>,
+}
+
+/// Counts every sampled revision of the repository at `path`, oldest first
+pub fn collect_history(
+    path: &Path,
+    sampling: HistorySampling,
+    every: usize,
+    options: &ScanOptions,
+) -> Result<Vec<HistoryPoint>, String> {
+    let commits = match sampling {
+        HistorySampling::Tags => git::tags(path)?,
+        HistorySampling::Commits | HistorySampling::Monthly => {
+            sample_commits(git::log(path)?, sampling, every)
+        }
+    };
+
+    commits
+        .into_iter()
+        .map(|commit| {
+            if options.debug {
+                eprintln!("Counting {} ({})", commit.revision, commit.date);
+            }
+
+            let blobs = git::revision_files(path, &commit.revision, is_candidate)?;
+            let languages = scan_blobs(&blobs, options)
+                .languages
+                .into_iter()
+                .sorted_by(|a, b| Ord::cmp(&b.1.code, &a.1.code))
+                .collect();
+
+            Ok(HistoryPoint { commit, languages })
+        })
+        .collect()
+}
+
+/// Picks commits out of a newest first log, returning them oldest first. HEAD is always kept.
+fn sample_commits(log: Vec<Commit>, sampling: HistorySampling, every: usize) -> Vec<Commit> {
+    let mut commits = match sampling {
+        HistorySampling::Commits => log.into_iter().step_by(every.max(1)).collect::<Vec<_>>(),
+        // The first commit seen for each month is its last since the log is newest first
+        HistorySampling::Monthly => log
+            .into_iter()
+            .dedup_by(|a, b| a.date.get(..7) == b.date.get(..7))
+            .collect(),
+        HistorySampling::Tags => log,
+    };
+    commits.reverse();
+
+    commits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn log() -> Vec<Commit> {
+        [
+            ("e", "2024-03-02"),
+            ("d", "2024-02-20"),
+            ("c", "2024-02-01"),
+            ("b", "2024-01-31"),
+            ("a", "2023-01-31"),
+        ]
+        .into_iter()
+        .map(|(revision, date)| Commit {
+            revision: revision.to_string(),
+            date: date.to_string(),
+        })
+        .collect()
+    }
+
+    #[test_case(HistorySampling::Commits, 1, &["a", "b", "c", "d", "e"])]
+    #[test_case(HistorySampling::Commits, 2, &["a", "c", "e"])]
+    #[test_case(HistorySampling::Commits, 3, &["b", "e"])]
+    #[test_case(HistorySampling::Commits, 10, &["e"])]
+    #[test_case(HistorySampling::Monthly, 1, &["a", "b", "d", "e"])]
+    fn test_sample_commits(sampling: HistorySampling, every: usize, expected: &[&str]) {
+        let actual = sample_commits(log(), sampling, every)
+            .into_iter()
+            .map(|commit| commit.revision)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,7 +1,15 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 use termcolor::Color;
 
+use crate::comments::{BlockComment, StringSyntax, strip_comments};
+
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum Language {
     Ada,
@@ -34,14 +42,17 @@ pub enum Language {
     Makefile,
     #[cfg(feature = "mars-lang")]
     Mars,
+    Matlab,
     Meson,
     Nim,
     Nix,
     Nu,
+    ObjectiveC,
     OCaml,
     Perl,
     Php,
     Powershell,
+    Prolog,
     Protobuf,
     Python,
     Qml,
@@ -56,10 +67,13 @@ pub enum Language {
     Svelte,
     TypeScript,
     V,
+    Verilog,
     VisualBasic,
     Vue,
     Xaml,
     Zig,
+    /// Defined in the config file, indexes into the registered custom languages
+    Custom(usize),
 }
 
 impl Display for Language {
@@ -74,12 +88,15 @@ impl Display for Language {
             Language::Glsl => "GLSL",
             Language::Hcl => "HCL",
             Language::Html => "HTML",
+            Language::Matlab => "MATLAB",
+            Language::ObjectiveC => "Objective-C",
             Language::Php => "PHP",
             Language::Qml => "QML",
             Language::Scss => "SCSS",
             Language::Sql => "SQL",
             Language::VisualBasic => "Visual Basic",
             Language::Xaml => "XAML",
+            Language::Custom(_) => self.custom().map_or("Unknown", |custom| custom.name),
             _ => &format!("{:?}", self),
         };
         write!(f, "{}", name)
@@ -87,6 +104,94 @@ impl Display for Language {
 }
 
 impl Language {
+    pub const ALL: &[Language] = &[
+        Language::Ada,
+        Language::Antlr,
+        Language::Assembly,
+        Language::Astro,
+        Language::C,
+        Language::CMake,
+        Language::Cobol,
+        Language::CPlusPlus,
+        Language::CSharp,
+        Language::Css,
+        Language::D,
+        Language::Dart,
+        Language::Dockerfile,
+        Language::Fish,
+        Language::Fortran,
+        Language::FSharp,
+        Language::GDScript,
+        Language::Gherkin,
+        Language::Glsl,
+        Language::Go,
+        Language::Haskell,
+        Language::Hcl,
+        Language::Html,
+        Language::Java,
+        Language::JavaScript,
+        Language::Julia,
+        Language::Lua,
+        Language::Makefile,
+        #[cfg(feature = "mars-lang")]
+        Language::Mars,
+        Language::Matlab,
+        Language::Meson,
+        Language::Nim,
+        Language::Nix,
+        Language::Nu,
+        Language::ObjectiveC,
+        Language::OCaml,
+        Language::Perl,
+        Language::Php,
+        Language::Powershell,
+        Language::Prolog,
+        Language::Protobuf,
+        Language::Python,
+        Language::Qml,
+        Language::Razor,
+        Language::React,
+        Language::Ruby,
+        Language::Rust,
+        Language::Sass,
+        Language::Scss,
+        Language::Shell,
+        Language::Sql,
+        Language::Svelte,
+        Language::TypeScript,
+        Language::V,
+        Language::Verilog,
+        Language::VisualBasic,
+        Language::Vue,
+        Language::Xaml,
+        Language::Zig,
+    ];
+
+    /// Looks up a language by its display name or variant name, ignoring case
+    pub fn from_name(name: &str) -> Option<Language> {
+        Language::ALL
+            .iter()
+            .copied()
+            .find(|lang| {
+                lang.to_string().eq_ignore_ascii_case(name)
+                    || format!("{:?}", lang).eq_ignore_ascii_case(name)
+            })
+            .or_else(|| {
+                CUSTOM_LANGUAGES
+                    .get()?
+                    .iter()
+                    .position(|custom| custom.name.eq_ignore_ascii_case(name))
+                    .map(Language::Custom)
+            })
+    }
+
+    pub fn custom(&self) -> Option<&'static CustomLanguage> {
+        match self {
+            Language::Custom(index) => CUSTOM_LANGUAGES.get()?.get(*index),
+            _ => None,
+        }
+    }
+
     pub fn color(&self) -> Color {
         match self {
             // The color comments are just for nvim-highlight-colors
@@ -122,14 +227,17 @@ impl Language {
             Language::Makefile => Color::Rgb(63, 63, 63), // rgb(63, 63, 63)
             #[cfg(feature = "mars-lang")]
             Language::Mars => Color::Rgb(132, 58, 49), // rgb(132, 58, 49)
+            Language::Matlab => Color::Rgb(225, 103, 55), // rgb(225, 103, 55)
             Language::Meson => Color::Rgb(57, 32, 124), // rgb(57, 32, 124)
             Language::Nim => Color::Rgb(255, 233, 83), // rgb(255, 233, 83)
             Language::Nix => Color::Rgb(126, 126, 255), // rgb(126, 126, 255)
             Language::Nu => Color::Rgb(78, 154, 6), // rgb(78, 154, 6)
+            Language::ObjectiveC => Color::Rgb(67, 142, 255), // rgb(67, 142, 255)
             Language::OCaml => Color::Rgb(238, 106, 26), // rgb(238, 106, 26)
             Language::Perl => Color::Rgb(57, 69, 126), // rgb(57, 69, 126)
             Language::Php => Color::Rgb(119, 123, 179), // rgb(119, 123, 179)
             Language::Powershell => Color::Rgb(83, 145, 254), // rgb(83, 145, 254)
+            Language::Prolog => Color::Rgb(116, 40, 60), // rgb(116, 40, 60)
             Language::Protobuf => Color::Rgb(66, 133, 244), // rgb(66, 133, 244)
             Language::Python => Color::Rgb(255, 221, 84), // rgb(255, 221, 84)
             Language::Qml => Color::Rgb(44, 222, 133), // rgb(44, 222, 133)
@@ -143,35 +251,129 @@ impl Language {
             Language::Svelte => Color::Rgb(255, 62, 0), // rgb(255, 62, 0)
             Language::TypeScript => Color::Rgb(49, 120, 198), // rgb(49, 120, 198)
             Language::V => Color::Rgb(60, 86, 109), // rgb(60, 86, 109)
+            Language::Verilog => Color::Rgb(178, 183, 248), // rgb(178, 183, 248)
             Language::Vue => Color::Rgb(65, 184, 131), // rgb(65, 184, 131)
             Language::Zig => Color::Rgb(247, 164, 66), // rgb(247, 164, 66)
+            Language::Custom(_) => self
+                .custom()
+                .map_or(Color::Rgb(128, 128, 128), |custom| custom.color),
         }
     }
 }
 
-pub fn determine_language(path: PathBuf) -> Option<Language> {
+/// A language defined in the config file. The strings live for the rest of the program, so they
+/// can be used in place of the built-in comment tables.
+pub struct CustomLanguage {
+    pub name: &'static str,
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [BlockComment],
+    pub strings: &'static [StringSyntax],
+    pub color: Color,
+}
+
+static CUSTOM_LANGUAGES: OnceLock<Vec<CustomLanguage>> = OnceLock::new();
+
+/// Registers the languages `Language::Custom` refers to. Only the first call has any effect.
+pub fn set_custom_languages(languages: Vec<CustomLanguage>) {
+    let _ = CUSTOM_LANGUAGES.set(languages);
+}
+
+/// Extra file names and extensions mapped to languages, e.g. from the config file
+#[derive(Default)]
+pub struct LanguageMappings {
+    pub filenames: HashMap<String, Language>,
+    pub extensions: HashMap<String, Language>,
+}
+
+static MAPPINGS: OnceLock<LanguageMappings> = OnceLock::new();
+
+/// Registers custom mappings, which take priority over the built-in ones. Only the first call has
+/// any effect.
+pub fn set_mappings(mappings: LanguageMappings) {
+    let _ = MAPPINGS.set(mappings);
+}
+
+fn mapped_language(path: &Path) -> Option<Language> {
+    let mappings = MAPPINGS.get()?;
+
+    if let Some(filename) = path.file_name()
+        && let Some(lang) = mappings.filenames.get(filename.to_string_lossy().as_ref())
+    {
+        return Some(*lang);
+    }
+
+    let extension = path.extension()?;
+    mappings
+        .extensions
+        .get(extension.to_string_lossy().as_ref())
+        .copied()
+}
+
+/// What the path alone says about a file's language
+enum PathMatch {
+    Language(Language),
+    /// C or C++ header, the contents decide
+    Header,
+    /// The extension is shared by several languages, the contents decide
+    Ambiguous(&'static Heuristic),
+    /// No extension or an unknown one, the contents might have a modeline or shebang
+    Unknown,
+}
+
+/// Whether the path alone says which language a file could be. Other files need a modeline or
+/// shebang.
+pub fn is_known_path(path: &Path) -> bool {
+    !matches!(match_path(path), PathMatch::Unknown)
+}
+
+/// Determines a file's language from its path and contents. Editor modelines take priority over
+/// everything else. `siblings` settles C and C++ headers that could be either.
+pub fn determine_language(
+    path: &Path,
+    contents: &str,
+    siblings: &SiblingSources,
+) -> Option<Language> {
+    let path_match = match_path(path);
+
+    if let Some(lang) = language_from_modeline(contents) {
+        return Some(lang);
+    }
+
+    match path_match {
+        PathMatch::Language(lang) => Some(lang),
+        PathMatch::Header => Some(disambiguate_header(path, contents, siblings)),
+        PathMatch::Ambiguous(heuristic) => heuristic.apply(contents),
+        PathMatch::Unknown => language_from_shebang(contents.lines().next()?),
+    }
+}
+
+fn match_path(path: &Path) -> PathMatch {
+    if let Some(lang) = mapped_language(path) {
+        return PathMatch::Language(lang);
+    }
+
     match path.file_name() {
         Some(os_str) => match os_str.as_encoded_bytes() {
-            b"CMakeLists.txt" => return Some(Language::CMake),
-            b"Makefile" | b"makefile" => return Some(Language::Makefile),
+            b"CMakeLists.txt" => return PathMatch::Language(Language::CMake),
+            b"Makefile" | b"makefile" => return PathMatch::Language(Language::Makefile),
             _ => {
                 let filename_string = os_str.to_string_lossy();
                 if filename_string.starts_with("Containerfile")
                     || filename_string.starts_with("Dockerfile")
                 {
-                    return Some(Language::Dockerfile);
+                    return PathMatch::Language(Language::Dockerfile);
                 }
                 if filename_string.starts_with("meson.") {
-                    return Some(Language::Meson);
+                    return PathMatch::Language(Language::Meson);
                 }
             }
         },
-        None => return None,
+        None => return PathMatch::Unknown,
     }
 
     match path.extension() {
-        None => None,
-        Some(os_str) => Some(match os_str.as_encoded_bytes() {
+        None => PathMatch::Unknown,
+        Some(os_str) => PathMatch::Language(match os_str.as_encoded_bytes() {
             b"adb" => Language::Ada,
             b"ads" => Language::Ada,
             b"asm" => Language::Assembly,
@@ -188,7 +390,7 @@ pub fn determine_language(path: PathBuf) -> Option<Language> {
             b"cs" => Language::CSharp,
             b"cshtml" => Language::Razor,
             b"css" => Language::Css,
-            b"d" => Language::D,
+            b"d" => return PathMatch::Ambiguous(&D_HEURISTIC),
             b"dart" => Language::Dart,
             b"f" => Language::Fortran,
             b"for" => Language::Fortran,
@@ -199,13 +401,13 @@ pub fn determine_language(path: PathBuf) -> Option<Language> {
             b"feature" => Language::Gherkin,
             b"fish" => Language::Fish,
             b"frag" => Language::Glsl,
-            b"fs" => Language::FSharp,
+            b"fs" => return PathMatch::Ambiguous(&FS_HEURISTIC),
             b"gd" => Language::GDScript,
             b"geom" => Language::Glsl,
             b"glsl" => Language::Glsl,
             b"go" => Language::Go,
             b"g4" => Language::Antlr,
-            b"h" => disambiguate_header(path),
+            b"h" => return PathMatch::Header,
             b"hpp" => Language::CPlusPlus,
             b"hs" => Language::Haskell,
             b"html" => Language::Html,
@@ -215,6 +417,7 @@ pub fn determine_language(path: PathBuf) -> Option<Language> {
             b"js" => Language::JavaScript,
             b"jsx" => Language::React,
             b"lua" => Language::Lua,
+            b"m" => return PathMatch::Ambiguous(&M_HEURISTIC),
             #[cfg(feature = "mars-lang")]
             b"mars" => Language::Mars,
             b"ml" => Language::OCaml,
@@ -223,7 +426,7 @@ pub fn determine_language(path: PathBuf) -> Option<Language> {
             b"nix" => Language::Nix,
             b"nu" => Language::Nu,
             b"php" => Language::Php,
-            b"pl" => Language::Perl,
+            b"pl" => return PathMatch::Ambiguous(&PL_HEURISTIC),
             b"proto" => Language::Protobuf,
             b"ps1" => Language::Powershell,
             b"py" => Language::Python,
@@ -237,38 +440,589 @@ pub fn determine_language(path: PathBuf) -> Option<Language> {
             b"scss" => Language::Scss,
             b"sh" => Language::Shell,
             b"sql" => Language::Sql,
+            b"sv" => Language::Verilog,
             b"svelte" => Language::Svelte,
             b"tesc" => Language::Glsl,
             b"tese" => Language::Glsl,
             b"tf" => Language::Hcl,
-            b"ts" => Language::TypeScript,
+            b"ts" => return PathMatch::Ambiguous(&TS_HEURISTIC),
             b"tsx" => Language::React,
-            b"v" => Language::V,
+            b"v" => return PathMatch::Ambiguous(&V_HEURISTIC),
             b"vb" => Language::VisualBasic,
             b"vert" => Language::Glsl,
+            b"vh" => Language::Verilog,
             b"vue" => Language::Vue,
             b"xaml" => Language::Xaml,
             b"zig" => Language::Zig,
             b"zsh" => Language::Shell,
-            _ => return None,
+            _ => return PathMatch::Unknown,
         }),
     }
 }
 
-fn disambiguate_header(path: PathBuf) -> Language {
-    let contents = std::fs::read_to_string(path).unwrap();
-
-    if contents.contains("<string>")
-        || contents.contains("<vector>")
-        || contents.contains("<utilty>")
-        || contents.contains("<memory>")
-        || contents.contains("<iostream>")
-        || contents.contains("template")
-        || contents.contains("namespace")
-        || contents.contains("extern \"C\"")
+/// Weighted content rules for an extension shared by several languages. The language with the
+/// highest total weight of matching rules wins, ties go to the default.
+struct Heuristic {
+    /// `None` means the file isn't code, e.g. Qt translation files using `.ts`
+    default: Option<Language>,
+    rules: &'static [Rule],
+}
+
+struct Rule {
+    lang: Option<Language>,
+    pattern: Pattern,
+    weight: u32,
+}
+
+enum Pattern {
+    /// Anywhere in the file
+    Contains(&'static str),
+    /// At the start of a line, ignoring indentation
+    LineStart(&'static str),
+    /// At the end of a line, ignoring trailing whitespace
+    LineEnd(&'static str),
+}
+
+impl Pattern {
+    fn matches(&self, contents: &str) -> bool {
+        match self {
+            Pattern::Contains(needle) => contents.contains(needle),
+            Pattern::LineStart(prefix) => contents
+                .lines()
+                .any(|line| line.trim_start().starts_with(prefix)),
+            Pattern::LineEnd(suffix) => contents
+                .lines()
+                .any(|line| line.trim_end().ends_with(suffix)),
+        }
+    }
+}
+
+impl Heuristic {
+    fn apply(&self, contents: &str) -> Option<Language> {
+        let mut scores: Vec<(Option<Language>, u32)> = vec![(self.default, 0)];
+        for rule in self.rules {
+            if !rule.pattern.matches(contents) {
+                continue;
+            }
+            match scores.iter_mut().find(|(lang, _)| *lang == rule.lang) {
+                Some((_, score)) => *score += rule.weight,
+                None => scores.push((rule.lang, rule.weight)),
+            }
+        }
+
+        // max_by_key returns the last maximum, so go in reverse to prefer the default on ties
+        scores
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, score)| *score)
+            .and_then(|(lang, _)| lang)
+    }
+}
+
+const fn rule(lang: Language, pattern: Pattern, weight: u32) -> Rule {
+    Rule {
+        lang: Some(lang),
+        pattern,
+        weight,
+    }
+}
+
+static D_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::D),
+    rules: &[
+        rule(Language::D, Pattern::LineStart("module "), 3),
+        rule(Language::D, Pattern::LineStart("import "), 3),
+        rule(Language::D, Pattern::Contains("void main("), 2),
+        rule(Language::D, Pattern::LineEnd(";"), 1),
+        // Dependency files generated by compilers, e.g. `main.o: main.c main.h \`
+        rule(Language::Makefile, Pattern::Contains(".o:"), 4),
+        rule(Language::Makefile, Pattern::LineEnd(" \\"), 2),
+    ],
+};
+
+static FS_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::FSharp),
+    rules: &[
+        rule(Language::FSharp, Pattern::LineStart("let "), 2),
+        rule(Language::FSharp, Pattern::LineStart("open "), 3),
+        rule(Language::FSharp, Pattern::LineStart("module "), 3),
+        rule(Language::FSharp, Pattern::LineStart("namespace "), 3),
+        rule(Language::FSharp, Pattern::Contains("|>"), 2),
+        rule(Language::Glsl, Pattern::LineStart("#version "), 5),
+        rule(Language::Glsl, Pattern::LineStart("precision "), 3),
+        rule(Language::Glsl, Pattern::LineStart("uniform "), 3),
+        rule(Language::Glsl, Pattern::Contains("gl_FragColor"), 5),
+        rule(Language::Glsl, Pattern::Contains("void main("), 2),
+        rule(Language::Glsl, Pattern::Contains("vec4"), 2),
+    ],
+};
+
+static M_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::ObjectiveC),
+    rules: &[
+        rule(Language::ObjectiveC, Pattern::LineStart("#import "), 5),
+        rule(Language::ObjectiveC, Pattern::LineStart("#include "), 2),
+        rule(Language::ObjectiveC, Pattern::LineStart("@interface"), 5),
+        rule(
+            Language::ObjectiveC,
+            Pattern::LineStart("@implementation"),
+            5,
+        ),
+        rule(Language::ObjectiveC, Pattern::LineStart("@end"), 3),
+        rule(Language::ObjectiveC, Pattern::Contains("NSString"), 3),
+        rule(Language::Matlab, Pattern::LineStart("function "), 3),
+        rule(Language::Matlab, Pattern::LineStart("%"), 2),
+        rule(Language::Matlab, Pattern::LineStart("elseif "), 2),
+        rule(Language::Matlab, Pattern::Contains("disp("), 2),
+        rule(Language::Matlab, Pattern::Contains("zeros("), 2),
+    ],
+};
+
+static PL_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::Perl),
+    rules: &[
+        rule(Language::Perl, Pattern::LineStart("use strict"), 5),
+        rule(Language::Perl, Pattern::LineStart("use warnings"), 5),
+        rule(Language::Perl, Pattern::LineStart("my "), 3),
+        rule(Language::Perl, Pattern::LineStart("sub "), 3),
+        rule(Language::Perl, Pattern::Contains("$_"), 2),
+        rule(Language::Prolog, Pattern::LineStart(":- "), 5),
+        rule(Language::Prolog, Pattern::Contains(":-"), 3),
+        rule(Language::Prolog, Pattern::LineEnd(")."), 2),
+        rule(Language::Prolog, Pattern::LineStart("%"), 1),
+    ],
+};
+
+static TS_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::TypeScript),
+    rules: &[
+        rule(Language::TypeScript, Pattern::LineStart("import "), 3),
+        rule(Language::TypeScript, Pattern::LineStart("export "), 3),
+        rule(Language::TypeScript, Pattern::LineStart("interface "), 2),
+        rule(Language::TypeScript, Pattern::Contains(": string"), 2),
+        rule(Language::TypeScript, Pattern::LineStart("function "), 1),
+        rule(Language::TypeScript, Pattern::LineStart("const "), 1),
+        // Qt Linguist translation files are XML
+        Rule {
+            lang: None,
+            pattern: Pattern::LineStart("<?xml"),
+            weight: 3,
+        },
+        Rule {
+            lang: None,
+            pattern: Pattern::LineStart("<!DOCTYPE TS>"),
+            weight: 10,
+        },
+        Rule {
+            lang: None,
+            pattern: Pattern::LineStart("<TS "),
+            weight: 10,
+        },
+        Rule {
+            lang: None,
+            pattern: Pattern::Contains("<context>"),
+            weight: 5,
+        },
+    ],
+};
+
+static V_HEURISTIC: Heuristic = Heuristic {
+    default: Some(Language::V),
+    rules: &[
+        rule(Language::V, Pattern::LineStart("fn "), 3),
+        rule(Language::V, Pattern::LineStart("pub fn "), 3),
+        rule(Language::V, Pattern::LineStart("import "), 2),
+        rule(Language::V, Pattern::LineStart("struct "), 2),
+        rule(Language::V, Pattern::Contains(":="), 2),
+        rule(Language::Verilog, Pattern::Contains("endmodule"), 5),
+        rule(Language::Verilog, Pattern::LineStart("`timescale"), 5),
+        rule(Language::Verilog, Pattern::LineStart("always @"), 3),
+        rule(Language::Verilog, Pattern::LineStart("assign "), 2),
+        rule(Language::Verilog, Pattern::LineStart("input "), 2),
+        rule(Language::Verilog, Pattern::LineStart("output "), 2),
+    ],
+};
+
+/// Detects Vim modelines in the first or last few lines, and Emacs `-*- mode: ... -*-` headers
+fn language_from_modeline(contents: &str) -> Option<Language> {
+    // Same as Vim's default 'modelines' setting
+    const MODELINE_LINES: usize = 5;
+
+    // Emacs only looks at the second line when the first is a shebang
+    let mut lines = contents.lines();
+    let first = lines.next()?;
+    let second = lines.next().filter(|_| first.starts_with("#!"));
+    if let Some(lang) = [Some(first), second]
+        .into_iter()
+        .flatten()
+        .find_map(emacs_mode)
     {
-        return Language::CPlusPlus;
+        return Some(lang);
+    }
+
+    // Walk back from the end rather than splitting the whole file into lines
+    let head = contents.lines().take(MODELINE_LINES);
+    let mut tail = contents
+        .rsplit_terminator('\n')
+        .take(MODELINE_LINES)
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect::<Vec<_>>();
+    tail.reverse();
+    head.chain(tail).find_map(vim_filetype)
+}
+
+fn vim_filetype(line: &str) -> Option<Language> {
+    let options = ["vim:", "vi:", "ex:", "Vim:"]
+        .into_iter()
+        .find_map(|marker| {
+            let index = line.find(marker)?;
+            // The marker has to start the line or follow whitespace
+            if index > 0 && !line[..index].ends_with(char::is_whitespace) {
+                return None;
+            }
+            Some(&line[index + marker.len()..])
+        })?;
+    let options = options.trim_start();
+    let options = options
+        .strip_prefix("set ")
+        .or_else(|| options.strip_prefix("se "))
+        .unwrap_or(options);
+
+    options
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            option
+                .strip_prefix("ft=")
+                .or_else(|| option.strip_prefix("filetype="))
+        })
+        .and_then(language_from_editor_name)
+}
+
+fn emacs_mode(line: &str) -> Option<Language> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (variables, _) = rest.split_once("-*-")?;
+
+    let mode = if variables.contains(':') {
+        variables.split(';').find_map(|variable| {
+            let (key, value) = variable.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case("mode")
+                .then_some(value.trim())
+        })?
+    } else {
+        variables.trim()
+    };
+
+    language_from_editor_name(mode)
+}
+
+/// Maps Vim filetypes and Emacs major modes to languages
+fn language_from_editor_name(name: &str) -> Option<Language> {
+    let name = name.to_lowercase();
+    let name = name.strip_suffix("-mode").unwrap_or(&name);
+
+    Some(match name {
+        "asm" | "nasm" => Language::Assembly,
+        "bash" | "sh" | "shell-script" | "zsh" => Language::Shell,
+        "c++" | "cpp" => Language::CPlusPlus,
+        "cperl" => Language::Perl,
+        "cs" | "csharp" => Language::CSharp,
+        "f90" | "f95" => Language::Fortran,
+        "fsharp" => Language::FSharp,
+        "js" | "js2" => Language::JavaScript,
+        "make" | "makefile-gmake" => Language::Makefile,
+        "proto" | "protobuf" => Language::Protobuf,
+        "ps1" => Language::Powershell,
+        "terraform" => Language::Hcl,
+        "tuareg" => Language::OCaml,
+        "vb" | "vbnet" => Language::VisualBasic,
+        _ => return Language::from_name(name),
+    })
+}
+
+/// Detects scripts by their interpreter, e.g. `#!/bin/bash` or `#!/usr/bin/env -S python3 -u`
+fn language_from_shebang(line: &str) -> Option<Language> {
+    let mut args = line.strip_prefix("#!")?.split_whitespace();
+
+    let mut interpreter = args.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        // Skip env's own options and any variable assignments
+        interpreter = args.find(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+        interpreter = interpreter.rsplit('/').next()?;
+    }
+
+    // Drop version suffixes like python3.12 or lua5.4
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    Some(match interpreter {
+        "ash" | "bash" | "dash" | "ksh" | "mksh" | "sh" | "zsh" => Language::Shell,
+        "dart" => Language::Dart,
+        "fish" => Language::Fish,
+        "julia" => Language::Julia,
+        "lua" | "luajit" => Language::Lua,
+        "make" => Language::Makefile,
+        "node" | "nodejs" => Language::JavaScript,
+        "nu" => Language::Nu,
+        "ocaml" => Language::OCaml,
+        "perl" => Language::Perl,
+        "php" => Language::Php,
+        "pwsh" | "powershell" => Language::Powershell,
+        "pypy" | "python" => Language::Python,
+        "rdmd" => Language::D,
+        "ruby" => Language::Ruby,
+        "runghc" | "runhaskell" => Language::Haskell,
+        "rust-script" => Language::Rust,
+        "ts-node" | "tsx" => Language::TypeScript,
+        _ => return None,
+    })
+}
+
+fn disambiguate_header(path: &Path, contents: &str, siblings: &SiblingSources) -> Language {
+    let (c_score, cpp_score) = score_header(contents);
+
+    match c_score.cmp(&cpp_score) {
+        Ordering::Greater => Language::C,
+        Ordering::Less => Language::CPlusPlus,
+        Ordering::Equal => siblings.language(path).unwrap_or(Language::C),
+    }
+}
+
+/// Counts C-only and C++-only constructs in a header, ignoring comments and anything behind
+/// `#ifdef __cplusplus` guards, since those are exactly what C headers use to support C++
+fn score_header(contents: &str) -> (usize, usize) {
+    let code = strip_comments(contents, Language::CPlusPlus);
+
+    let mut c_score = 0;
+    let mut cpp_score = 0;
+
+    // Depth of nested #if directives, and the depth of the __cplusplus guard being skipped
+    let mut depth = 0;
+    let mut guard_depth = None;
+
+    for line in code.lines() {
+        let line = line.trim();
+
+        if let Some(directive) = line.strip_prefix('#') {
+            let directive = directive.trim_start();
+            if directive.starts_with("if") {
+                depth += 1;
+                let is_cplusplus_guard = directive.contains("__cplusplus")
+                    && !directive.starts_with("ifndef")
+                    && !directive.contains('!');
+                if guard_depth.is_none() && is_cplusplus_guard {
+                    guard_depth = Some(depth);
+                }
+            } else if directive.starts_with("el") {
+                if guard_depth == Some(depth) {
+                    guard_depth = None;
+                }
+            } else if directive.starts_with("endif") {
+                if guard_depth == Some(depth) {
+                    guard_depth = None;
+                }
+                depth -= 1;
+            } else if guard_depth.is_none()
+                && let Some(header) = directive.strip_prefix("include")
+                && let Some((_, name)) = header.split_once('<')
+                && let Some((name, _)) = name.split_once('>')
+                && !name.contains('.')
+            {
+                // C++ standard library headers have no extension
+                cpp_score += 1;
+            }
+            continue;
+        }
+
+        if guard_depth.is_some() {
+            continue;
+        }
+
+        if line.contains("::") || line.starts_with("extern \"C\"") {
+            cpp_score += 1;
+        }
+        if ["public:", "protected:", "private:"]
+            .iter()
+            .any(|label| line.starts_with(label))
+        {
+            cpp_score += 1;
+        }
+
+        for token in line.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            match token {
+                "class" | "namespace" | "operator" | "template" | "typename" | "virtual" => {
+                    cpp_score += 1
+                }
+                "_Bool" | "_Generic" | "_Noreturn" | "restrict" => c_score += 1,
+                _ => {}
+            }
+        }
     }
 
-    Language::C
+    (c_score, cpp_score)
+}
+
+/// How many C and C++ source files each directory holds, to settle headers that could be either
+#[derive(Default)]
+pub struct SiblingSources(HashMap<PathBuf, (usize, usize)>);
+
+impl SiblingSources {
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a Path>) -> SiblingSources {
+        let mut counts: HashMap<PathBuf, (usize, usize)> = HashMap::new();
+        for path in paths {
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            match path.extension().map(|e| e.as_encoded_bytes()) {
+                Some(b"c") => counts.entry(dir.to_path_buf()).or_default().0 += 1,
+                Some(b"cc" | b"cpp" | b"cxx") => {
+                    counts.entry(dir.to_path_buf()).or_default().1 += 1
+                }
+                _ => {}
+            }
+        }
+
+        SiblingSources(counts)
+    }
+
+    /// Picks C or C++ depending on which kind of source file is more common next to the header
+    fn language(&self, path: &Path) -> Option<Language> {
+        let (c_files, cpp_files) = self.0.get(path.parent()?)?;
+
+        match c_files.cmp(cpp_files) {
+            Ordering::Greater => Some(Language::C),
+            Ordering::Less => Some(Language::CPlusPlus),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("C++", Some(Language::CPlusPlus))]
+    #[test_case("cplusplus", Some(Language::CPlusPlus))]
+    #[test_case("visual basic", Some(Language::VisualBasic))]
+    #[test_case("rust", Some(Language::Rust))]
+    #[test_case("Brainfuck", None)]
+    fn test_from_name(name: &str, expected: Option<Language>) {
+        let actual = Language::from_name(name);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("#!/bin/bash\n", Some(Language::Shell))]
+    #[test_case("#!/bin/sh -e\n", Some(Language::Shell))]
+    #[test_case("#!/usr/bin/env python3\n", Some(Language::Python))]
+    #[test_case("#!/usr/bin/python3.12", Some(Language::Python))]
+    #[test_case("#!/usr/bin/env -S python3 -u\n", Some(Language::Python))]
+    #[test_case(
+        "#!/usr/bin/env -S NODE_ENV=production node\n",
+        Some(Language::JavaScript)
+    )]
+    #[test_case("#! /usr/bin/env ruby\n", Some(Language::Ruby))]
+    #[test_case("#!/usr/bin/make -f\n", Some(Language::Makefile))]
+    #[test_case("#!/usr/bin/env\n", None)]
+    #[test_case("#!/usr/bin/env brainfuck\n", None)]
+    #[test_case("print('hi')\n", None)]
+    fn test_language_from_shebang(line: &str, expected: Option<Language>) {
+        let actual = language_from_shebang(line);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("int x;\n// vim: set ft=cpp :\n", Some(Language::CPlusPlus) ; "vim set form")]
+    #[test_case("# vim: ts=4 filetype=python\nx = 1\n", Some(Language::Python) ; "vim option form")]
+    #[test_case("/* vi:set sw=2:ft=c: */\n", Some(Language::C) ; "vi marker")]
+    #[test_case("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n# vim: ft=sh\n", Some(Language::Shell) ; "vim last lines")]
+    #[test_case("1\n2\n3\n4\n5\n# vim: ft=sh\n7\n8\n9\n10\n11\n", None ; "vim middle of file")]
+    #[test_case("novim: ft=sh\n", None ; "vim marker inside word")]
+    #[test_case("# -*- mode: ruby -*-\n", Some(Language::Ruby) ; "emacs mode variable")]
+    #[test_case("#!/bin/sh\n# -*- mode: perl; coding: utf-8 -*-\n", Some(Language::Perl) ; "emacs after shebang")]
+    #[test_case("/* -*- C++ -*- */\n", Some(Language::CPlusPlus) ; "emacs bare mode")]
+    #[test_case("x\n# -*- mode: ruby -*-\n", None ; "emacs second line")]
+    #[test_case("int main() {}\n", None ; "no modeline")]
+    fn test_language_from_modeline(contents: &str, expected: Option<Language>) {
+        let actual = language_from_modeline(contents);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("main.d", "import std.stdio;\n\nvoid main() {\n    writeln(\"hi\");\n}\n", Some(Language::D) ; "d")]
+    #[test_case("main.d", "main.o: main.c main.h \\\n  util.h\n", Some(Language::Makefile) ; "d dependency file")]
+    #[test_case("main.fs", "module Main\n\nlet x = [1; 2] |> List.sum\n", Some(Language::FSharp) ; "fsharp")]
+    #[test_case("main.fs", "#version 330 core\nout vec4 color;\nvoid main() {\n    color = vec4(1.0);\n}\n", Some(Language::Glsl) ; "glsl fragment shader")]
+    #[test_case("main.m", "#import <Foundation/Foundation.h>\n\n@implementation Foo\n@end\n", Some(Language::ObjectiveC) ; "objective c")]
+    #[test_case("main.m", "% compute things\nfunction y = f(x)\n    y = zeros(x);\nend\n", Some(Language::Matlab) ; "matlab")]
+    #[test_case("main.pl", "use strict;\nuse warnings;\n\nmy $x = 1;\n", Some(Language::Perl) ; "perl")]
+    #[test_case("main.pl", "% facts\nparent(tom, bob).\nancestor(X, Y) :- parent(X, Y).\n", Some(Language::Prolog) ; "prolog")]
+    #[test_case("main.ts", "import { x } from './x';\n\nexport const y: string = x;\n", Some(Language::TypeScript) ; "typescript")]
+    #[test_case("main.ts", "<?xml version=\"1.0\"?>\n<!DOCTYPE TS>\n<TS version=\"2.1\">\n<context>\n</context>\n</TS>\n", None ; "qt translation")]
+    #[test_case("main.v", "module main\n\nfn main() {\n    x := 1\n}\n", Some(Language::V) ; "v")]
+    #[test_case("main.v", "module counter(input clk, output reg q);\n    always @(posedge clk) q <= ~q;\nendmodule\n", Some(Language::Verilog) ; "verilog")]
+    #[test_case("main.v", "", Some(Language::V) ; "empty file uses default")]
+    fn test_ambiguous_extension(path: &str, contents: &str, expected: Option<Language>) {
+        let actual = determine_language(Path::new(path), contents, &SiblingSources::default());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("configure.in", "dnl -*- mode: sh -*-\nAC_INIT\n", Some(Language::Shell) ; "modeline with unknown extension")]
+    #[test_case("deploy.tmpl", "#!/usr/bin/env python3\nprint('hi')\n", Some(Language::Python) ; "shebang with unknown extension")]
+    #[test_case("deploy", "#!/bin/bash\necho hi\n", Some(Language::Shell) ; "shebang without extension")]
+    #[test_case("notes.txt", "Nothing to see here\n", None ; "unknown extension")]
+    #[test_case("main.py", "# vim: ft=ruby\nputs 1\n", Some(Language::Ruby) ; "modeline overrides extension")]
+    fn test_determine_language(path: &str, contents: &str, expected: Option<Language>) {
+        let actual = determine_language(Path::new(path), contents, &SiblingSources::default());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("#include <stdio.h>\n\nint add(int a, int b);\n", Language::C ; "plain c")]
+    #[test_case("#include <vector>\n\nstd::vector<int> numbers();\n", Language::CPlusPlus ; "std header")]
+    #[test_case("#include <utility>\n", Language::CPlusPlus ; "utility header")]
+    #[test_case("namespace foo {\nint bar();\n}\n", Language::CPlusPlus ; "namespace")]
+    #[test_case("template <typename T>\nT max(T a, T b);\n", Language::CPlusPlus ; "template")]
+    #[test_case("/* this is not a template */\nint x;\n// namespace\n", Language::C ; "keywords in comments")]
+    #[test_case("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\nint x;\n\n#ifdef __cplusplus\n}\n#endif\n", Language::C ; "extern c guard")]
+    #[test_case("#if defined(__cplusplus)\nnamespace foo {}\n#else\nint _Generic_user(void * restrict p);\n#endif\n", Language::C ; "guard with else")]
+    #[test_case("extern \"C\" int x;\n", Language::CPlusPlus ; "unguarded extern c")]
+    #[test_case("class Foo {\npublic:\n    Foo();\n};\n", Language::CPlusPlus ; "class")]
+    fn test_disambiguate_header(contents: &str, expected: Language) {
+        let actual = disambiguate_header(
+            Path::new("/nonexistent/header.h"),
+            contents,
+            &SiblingSources::default(),
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("src/util.h", Language::CPlusPlus ; "more cpp siblings")]
+    #[test_case("lib/util.h", Language::C ; "more c siblings")]
+    #[test_case("include/util.h", Language::C ; "no siblings")]
+    fn test_sibling_sources(path: &str, expected: Language) {
+        let siblings = SiblingSources::new(
+            [
+                "src/a.cpp",
+                "src/b.cc",
+                "src/c.c",
+                "lib/a.c",
+                "include/util.h",
+            ]
+            .map(Path::new),
+        );
+
+        let actual = disambiguate_header(Path::new(path), "int add(int a, int b);\n", &siblings);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_all_languages_listed() {
+        for lang in Language::ALL {
+            assert_eq!(Language::from_name(&lang.to_string()), Some(*lang));
+        }
+    }
 }
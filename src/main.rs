@@ -1,15 +1,50 @@
-use std::{collections::HashMap, path::Path};
+use std::path::Path;
 
-use clap::Parser;
-use comments::CodeReader;
-use display::{print_results, print_results_compact, print_results_summary};
-use ignore::WalkBuilder;
+use cargo::{group_by_crate, workspace_crates};
+use clap::{
+    Arg, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum, parser::ValueSource,
+};
+use config::{Config, DisplayMode};
+use diff::{diff_files, scan_target};
+use display::{
+    ColorWhen, color_choice, print_crates, print_crates_csv, print_crates_json, print_diff,
+    print_diff_csv, print_diff_json, print_fetch, print_file_report, print_file_report_csv,
+    print_file_report_json, print_history, print_history_csv, print_history_json,
+    print_projects_csv, print_projects_json, print_results, print_results_bar,
+    print_results_compact, print_results_csv, print_results_json, print_results_summary,
+    print_skipped, print_tree, print_tree_csv, print_tree_json,
+};
+use fetch::Fetch;
+use history::{HistorySampling, collect_history};
 use itertools::Itertools;
-use lang::Language;
+use projects::{find_projects, group_by_project};
+use report::{SortColumn, sort_files};
+use scan::{Scan, ScanOptions, is_candidate, scan_blobs, scan_directory, scan_files};
+use tree::build_tree;
 
+mod cargo;
 mod comments;
+mod config;
+mod diff;
 mod display;
+mod fetch;
+mod git;
+mod history;
 mod lang;
+mod projects;
+mod report;
+mod scan;
+mod tree;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Colored terminal output
+    Text,
+    /// Machine-readable JSON
+    Json,
+    /// Comma-separated values
+    Csv,
+}
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -26,6 +61,18 @@ struct Args {
     #[arg(short, long)]
     debug: bool,
 
+    /// List every recognized file with its code, comment and blank line counts
+    #[arg(short = 'F', long, conflicts_with_all = ["all", "count", "compact", "summary"])]
+    files: bool,
+
+    /// Column to sort the file list by
+    #[arg(long, value_enum, default_value_t = SortColumn::Code, requires = "files")]
+    sort: SortColumn,
+
+    /// Only list the first N files after sorting
+    #[arg(short, long, requires = "files")]
+    top: Option<usize>,
+
     /// Display results in a single line
     #[arg(short, long, conflicts_with_all = ["all", "count", "summary"])]
     compact: bool,
@@ -34,6 +81,14 @@ struct Args {
     #[arg(short, long, conflicts_with = "summary")]
     max_width: Option<u16>,
 
+    /// Display every language in a single stacked bar with a legend below
+    #[arg(short, long, conflicts_with_all = ["all", "count", "compact", "summary", "files", "format", "history", "diff", "projects", "crates", "tree"])]
+    bar: bool,
+
+    /// Display the project's initials next to facts about it, in the style of neofetch
+    #[arg(long, conflicts_with_all = ["all", "count", "compact", "bar", "summary", "files", "format", "history", "diff", "projects", "crates", "tree"])]
+    fetch: bool,
+
     /// Display text summary of the top n languages
     #[arg(short, long, conflicts_with_all = ["all", "count", "compact"])]
     summary: bool,
@@ -46,19 +101,147 @@ struct Args {
     #[arg(short, long)]
     exclude: Option<Vec<String>>,
 
+    /// Output format, json and csv always include every found language
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text, conflicts_with_all = ["compact", "summary"])]
+    format: OutputFormat,
+
+    /// When to color the output
+    #[arg(long, value_enum, default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+
+    /// Number of threads to walk and count files with, 0 picks automatically
+    #[arg(short = 'j', long, default_value_t = 0)]
+    threads: usize,
+
     /// Include .gitignore files
     #[arg(short, long)]
     gitignore: bool,
 
+    /// Only count files tracked by git, walks the directory as usual outside of a repository
+    #[arg(long, conflicts_with = "gitignore")]
+    tracked: bool,
+
+    /// Count the files at a git revision (commit, branch or tag) instead of the working tree
+    #[arg(short, long, conflicts_with_all = ["gitignore", "tracked"])]
+    rev: Option<String>,
+
+    /// Count a sample of past revisions and show how the distribution changed over time
+    #[arg(long, value_enum, conflicts_with_all = ["files", "compact", "summary", "gitignore", "tracked", "rev"])]
+    history: Option<HistorySampling>,
+
+    /// Number of commits between samples for --history commits
+    #[arg(long, default_value_t = 100, requires = "history")]
+    every: usize,
+
+    /// Compare two directories or git revisions and show the change in code per language
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with_all = ["files", "compact", "summary", "tracked", "rev", "history"])]
+    diff: Option<Vec<String>>,
+
+    /// Show a separate distribution for each project found under the path, then the total
+    #[arg(short = 'p', long, conflicts_with_all = ["files", "compact", "summary", "rev", "history", "diff"])]
+    projects: bool,
+
+    /// Show the code in each crate of a Cargo workspace, split into source, build script,
+    /// benches, examples and tests
+    #[arg(long, conflicts_with_all = ["files", "compact", "summary", "rev", "history", "diff", "projects"])]
+    crates: bool,
+
+    /// Show the code in each directory as a tree, with its dominant language
+    #[arg(short = 'T', long, conflicts_with_all = ["files", "compact", "summary", "history", "diff", "projects", "crates"])]
+    tree: bool,
+
+    /// Number of directory levels to show in the tree
+    #[arg(long, default_value_t = 2, requires = "tree")]
+    depth: usize,
+
+    /// Ignore the user and project config files
+    #[arg(long)]
+    no_config: bool,
+
     /// Path to search
     path: Option<String>,
 }
 
+impl Args {
+    /// Fills in options that weren't given on the command line from the config
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches) {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if !from_cli("all") && !from_cli("count") {
+            if let Some(all) = config.all {
+                self.all = all;
+            }
+            if let Some(count) = config.count {
+                self.count = count;
+            }
+        }
+        if !from_cli("exclude") && config.exclude.is_some() {
+            self.exclude = config.exclude;
+        }
+        if !from_cli("max_width") && config.max_width.is_some() {
+            self.max_width = config.max_width;
+        }
+        if !from_cli("summary_length")
+            && let Some(summary_length) = config.summary_length
+        {
+            self.summary_length = summary_length;
+        }
+        if !from_cli("gitignore")
+            && let Some(gitignore) = config.gitignore
+        {
+            self.gitignore = gitignore;
+        }
+        if !from_cli("gitignore")
+            && !from_cli("tracked")
+            && let Some(tracked) = config.tracked
+        {
+            self.tracked = tracked;
+        }
+        if !from_cli("threads")
+            && let Some(threads) = config.threads
+        {
+            self.threads = threads;
+        }
+
+        let (id, display) = match config.display {
+            Some(DisplayMode::Compact) => ("compact", &mut self.compact),
+            Some(DisplayMode::Bar) => ("bar", &mut self.bar),
+            Some(DisplayMode::Fetch) => ("fetch", &mut self.fetch),
+            Some(DisplayMode::Summary) => ("summary", &mut self.summary),
+            Some(DisplayMode::Table) | None => return,
+        };
+
+        // Command line options that clap wouldn't accept together with the configured display,
+        // e.g. another display or --count with compact, replace it
+        let command = Args::command();
+        let conflicts = |a: &Arg, b: &Arg| {
+            command
+                .get_arg_conflicts_with(a)
+                .iter()
+                .any(|conflict| conflict.get_id() == b.get_id())
+        };
+        let display_arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == id)
+            .unwrap();
+        let replaced = command.get_arguments().any(|arg| {
+            from_cli(arg.get_id().as_str())
+                && (conflicts(display_arg, arg) || conflicts(arg, display_arg))
+        });
+        if !replaced {
+            *display = true;
+        }
+    }
+}
+
 fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    let pwd = match args.path {
-        Some(p) => Path::new(&p).canonicalize(),
+    let color = color_choice(args.color);
+
+    let pwd = match args.path.as_ref() {
+        Some(p) => Path::new(p).canonicalize(),
         None => std::env::current_dir(),
     };
     let pwd = match pwd {
@@ -69,61 +252,238 @@ fn main() {
         }
     };
 
-    let mut builder = WalkBuilder::new(pwd.clone());
-    if args.gitignore {
-        builder.git_ignore(false).ignore(false);
+    if !args.no_config {
+        let config = match Config::load(&pwd) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+        match config.custom_languages() {
+            Ok(languages) => lang::set_custom_languages(languages),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        }
+        match config.language_mappings() {
+            Ok(mappings) => lang::set_mappings(mappings),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        }
+        args.apply_config(config, &matches);
+    }
+
+    let options = ScanOptions {
+        exclude: args
+            .exclude
+            .unwrap_or_default()
+            .iter()
+            .map(|exclude| exclude.to_lowercase())
+            .collect(),
+        debug: args.debug,
+        collect_files: args.files
+            || args.diff.is_some()
+            || args.projects
+            || args.crates
+            || args.tree
+            || args.fetch,
+        gitignore: args.gitignore,
+        threads: args.threads,
+    };
+    if let Some(sampling) = args.history {
+        let history = match collect_history(&pwd, sampling, args.every, &options) {
+            Ok(history) => history,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+
+        match args.format {
+            OutputFormat::Text => print_history(history, &pwd, args.max_width, color),
+            OutputFormat::Json => print_history_json(history, &pwd),
+            OutputFormat::Csv => print_history_csv(history),
+        }
+        return;
+    }
+
+    if let Some(targets) = args.diff.as_ref() {
+        let (before, after) = (&targets[0], &targets[1]);
+        let scanned = scan_target(&pwd, before, &options)
+            .and_then(|before| Ok((before, scan_target(&pwd, after, &options)?)));
+        let ((before_root, before_scan), (after_root, after_scan)) = match scanned {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+        let diffs = diff_files(
+            &before_scan.files,
+            &before_root,
+            &after_scan.files,
+            &after_root,
+        );
+
+        match args.format {
+            OutputFormat::Text => print_diff(diffs, before, after, color),
+            OutputFormat::Json => print_diff_json(diffs, before, after),
+            OutputFormat::Csv => print_diff_csv(diffs),
+        }
+        return;
+    }
+
+    // Read the manifests before spending time on the scan
+    let crates = if args.crates {
+        match workspace_crates(&pwd) {
+            Ok(crates) => Some(crates),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let scan = if let Some(rev) = args.rev.as_ref() {
+        match git::revision_files(&pwd, rev, is_candidate) {
+            Ok(blobs) => scan_blobs(&blobs, &options),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        }
+    } else if args.tracked
+        && let Some(tracked) = git::tracked_files(&pwd)
+    {
+        scan_files(&tracked, &options)
+    } else {
+        if args.tracked && args.debug {
+            eprintln!(
+                "{}: not a git repository, counting every file",
+                pwd.to_string_lossy()
+            );
+        }
+        scan_directory(&pwd, &options)
     };
-    let walk = builder.build();
-
-    let mut results: HashMap<Language, usize> = HashMap::new();
-    'outer: for entry in walk {
-        match entry {
-            Err(e) => eprintln!("Error: {}", e),
-            Ok(entry) => {
-                if entry.file_type().unwrap().is_file() {
-                    let Some(lang) = lang::determine_language(entry.path().to_path_buf()) else {
-                        if args.debug {
-                            eprintln!("{}: unrecognized file", entry.path().to_string_lossy())
-                        }
-                        continue;
-                    };
-                    let reader =
-                        CodeReader::from_path(entry.path(), lang).expect("Unable to read file");
-                    let sloc = reader.sloc();
-
-                    if let std::collections::hash_map::Entry::Vacant(e) = results.entry(lang) {
-                        if let Some(ref arr) = args.exclude {
-                            for exclude in arr {
-                                if lang.to_string().to_lowercase() == exclude.to_lowercase() {
-                                    continue 'outer;
-                                }
-                            }
-                        };
-                        e.insert(sloc);
+    let Scan {
+        languages: results,
+        mut files,
+        skipped,
+    } = scan;
+
+    print_skipped(&skipped, &pwd);
+
+    if args.files {
+        sort_files(&mut files, args.sort);
+        if let Some(top) = args.top {
+            files.truncate(top);
+        }
+
+        match args.format {
+            OutputFormat::Text => print_file_report(files, &pwd, color),
+            OutputFormat::Json => print_file_report_json(files, &pwd),
+            OutputFormat::Csv => print_file_report_csv(files, &pwd),
+        }
+        return;
+    }
+
+    if args.tree {
+        let tree = build_tree(&files, &pwd, args.depth);
+
+        match args.format {
+            OutputFormat::Text => print_tree(tree, &pwd, color),
+            OutputFormat::Json => print_tree_json(tree),
+            OutputFormat::Csv => print_tree_csv(tree),
+        }
+        return;
+    }
+
+    if let Some(crates) = crates {
+        let crates = group_by_crate(&files, &crates, &pwd);
+
+        match args.format {
+            OutputFormat::Text => print_crates(crates, &pwd, color),
+            OutputFormat::Json => print_crates_json(crates, &pwd),
+            OutputFormat::Csv => print_crates_csv(crates, &pwd),
+        }
+        return;
+    }
+
+    let results = results
+        .into_iter()
+        .sorted_by(|a, b| Ord::cmp(&b.1.code, &a.1.code));
+
+    if args.projects {
+        let projects = group_by_project(&files, &find_projects(&pwd, args.gitignore), &pwd);
+        let results = results.collect();
+
+        match args.format {
+            OutputFormat::Json => print_projects_json(projects, results, &pwd),
+            OutputFormat::Csv => print_projects_csv(projects, results, &pwd),
+            OutputFormat::Text => {
+                let take = |languages: Vec<_>| {
+                    if args.all {
+                        languages
                     } else {
-                        *results.get_mut(&lang).unwrap() += sloc;
+                        languages.into_iter().take(args.count).collect()
                     }
+                };
 
-                    if args.debug {
-                        eprintln!(
-                            "{}: {}, {} lines",
-                            entry.path().to_string_lossy(),
-                            lang,
-                            sloc
-                        )
-                    }
+                for project in projects {
+                    print_results(
+                        take(project.languages),
+                        &format!("Language distribution for {:?}", project.root),
+                        args.max_width,
+                        color,
+                    );
+                    println!();
                 }
+                print_results(
+                    take(results),
+                    &format!("Total for {:?}", pwd),
+                    args.max_width,
+                    color,
+                );
             }
         }
+        return;
     }
 
-    let results = results.into_iter().sorted_by(|a, b| Ord::cmp(&b.1, &a.1));
-
-    if args.compact {
-        print_results_compact(results.collect(), args.max_width);
+    if args.format == OutputFormat::Json {
+        print_results_json(results.collect(), &pwd);
+        return;
+    } else if args.format == OutputFormat::Csv {
+        print_results_csv(results.collect());
+        return;
+    } else if args.compact {
+        print_results_compact(
+            results.map(|(lang, stats)| (lang, stats.code)).collect(),
+            args.max_width,
+            color,
+        );
+        return;
+    } else if args.fetch {
+        print_fetch(Fetch::new(&pwd, files.len(), results.collect()), color);
+        return;
+    } else if args.bar {
+        print_results_bar(
+            results.map(|(lang, stats)| (lang, stats.code)).collect(),
+            args.max_width,
+            color,
+        );
         return;
     } else if args.summary {
-        print_results_summary(results.collect(), args.summary_length);
+        print_results_summary(
+            results.map(|(lang, stats)| (lang, stats.code)).collect(),
+            args.summary_length,
+            color,
+        );
         return;
     }
 
@@ -133,5 +493,82 @@ fn main() {
         results.take(args.count).collect()
     };
 
-    print_results(results, &pwd, args.max_width);
+    print_results(
+        results,
+        &format!("Language distribution for {:?}", pwd),
+        args.max_width,
+        color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `cli` like main does, with `config` applied on top
+    fn parse(config: &str, cli: &[&str]) -> Args {
+        let matches = Args::command()
+            .try_get_matches_from(std::iter::once("projfetch").chain(cli.iter().copied()))
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        args.apply_config(toml::from_str(config).unwrap(), &matches);
+        args
+    }
+
+    #[test]
+    fn test_config_compact_replaced_by_count() {
+        let args = parse("display = \"compact\"", &["-n", "3"]);
+
+        assert!(!args.compact);
+        assert_eq!(args.count, 3);
+    }
+
+    #[test]
+    fn test_config_bar_replaced_by_format() {
+        let args = parse("display = \"bar\"", &["--format", "json"]);
+
+        assert!(!args.bar);
+        assert!(matches!(args.format, OutputFormat::Json));
+    }
+
+    #[test]
+    fn test_config_summary_replaced_by_max_width() {
+        let args = parse("display = \"summary\"", &["-m", "80"]);
+
+        assert!(!args.summary);
+        assert_eq!(args.max_width, Some(80));
+    }
+
+    #[test]
+    fn test_config_display_replaced_by_display() {
+        let args = parse("display = \"compact\"", &["--bar"]);
+
+        assert!(!args.compact);
+        assert!(args.bar);
+    }
+
+    #[test]
+    fn test_config_count_ignored_with_all() {
+        let args = parse("count = 5", &["--all"]);
+
+        assert!(args.all);
+        assert_eq!(args.count, 10);
+    }
+
+    #[test]
+    fn test_config_display_kept() {
+        let args = parse("display = \"compact\"", &["-m", "80"]);
+
+        assert!(args.compact);
+        assert_eq!(args.max_width, Some(80));
+    }
+
+    #[test]
+    fn test_config_count_applied() {
+        let args = parse("count = 5\nmax_width = 60", &[]);
+
+        assert!(!args.all);
+        assert_eq!(args.count, 5);
+        assert_eq!(args.max_width, Some(60));
+    }
 }
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+use itertools::Itertools;
+
+use crate::{comments::LineStats, lang::Language, report::FileReport};
+
+/// Files that mark the directory they're in as the root of a project
+const PROJECT_FILES: &[&str] = &["Cargo.toml", "package.json", "go.mod", "pyproject.toml"];
+const PROJECT_EXTENSIONS: &[&str] = &["csproj"];
+
+pub struct Project {
+    pub root: PathBuf,
+    /// Sorted from most to least code
+    pub languages: Vec<(Language, LineStats)>,
+}
+
+fn is_project_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| PROJECT_FILES.contains(&name))
+        || path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| PROJECT_EXTENSIONS.contains(&extension))
+}
+
+/// Finds the project roots under `root`, following the same ignore rules as the scan
+pub fn find_projects(root: &Path, gitignore: bool) -> Vec<PathBuf> {
+    let mut builder = WalkBuilder::new(root);
+    if gitignore {
+        builder.git_ignore(false).ignore(false);
+    };
+
+    let mut projects = Vec::new();
+    for entry in builder.build().flatten() {
+        let path = entry.path();
+        if entry.file_type().is_some_and(|t| t.is_dir()) {
+            // .git is hidden from the walk, so check for it from its parent
+            if path.join(".git").exists() {
+                projects.push(path.to_path_buf());
+            }
+        } else if is_project_file(path)
+            && let Some(parent) = path.parent()
+        {
+            projects.push(parent.to_path_buf());
+        }
+    }
+    projects.sort();
+    projects.dedup();
+
+    projects
+}
+
+/// Adds up the files of each project, a file belongs to the innermost project that contains it.
+/// Files outside of every project are put under `root`. Projects without files are left out.
+pub fn group_by_project(files: &[FileReport], projects: &[PathBuf], root: &Path) -> Vec<Project> {
+    let mut grouped: HashMap<&Path, HashMap<Language, LineStats>> = HashMap::new();
+    for file in files {
+        let project = projects
+            .iter()
+            .filter(|project| file.path.starts_with(project))
+            .max_by_key(|project| project.components().count())
+            .map_or(root, |project| project.as_path());
+
+        *grouped
+            .entry(project)
+            .or_default()
+            .entry(file.lang)
+            .or_default() += file.stats;
+    }
+
+    let mut projects = grouped
+        .into_iter()
+        .map(|(root, languages)| Project {
+            root: root.to_path_buf(),
+            languages: languages
+                .into_iter()
+                .sorted_by(|a, b| Ord::cmp(&b.1.code, &a.1.code))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    projects.sort_by(|a, b| a.root.cmp(&b.root));
+
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("crates/foo/Cargo.toml", true)]
+    #[test_case("web/package.json", true)]
+    #[test_case("src/App/App.csproj", true)]
+    #[test_case("src/main.rs", false)]
+    #[test_case("Cargo.lock", false)]
+    fn test_is_project_file(path: &str, expected: bool) {
+        let actual = is_project_file(Path::new(path));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_group_by_project() {
+        let file = FileReport::with_code;
+        let files = [
+            file("/repo/build.sh", Language::Shell, 5),
+            file("/repo/api/src/main.rs", Language::Rust, 100),
+            file("/repo/api/src/lib.rs", Language::Rust, 50),
+            file("/repo/web/index.ts", Language::TypeScript, 30),
+            file("/repo/web/tools/gen/main.go", Language::Go, 20),
+        ];
+        let projects = [
+            PathBuf::from("/repo/api"),
+            PathBuf::from("/repo/web"),
+            PathBuf::from("/repo/web/tools/gen"),
+            PathBuf::from("/repo/empty"),
+        ];
+
+        let grouped = group_by_project(&files, &projects, Path::new("/repo"));
+        let actual = grouped
+            .iter()
+            .map(|project| {
+                let code = project.languages.iter().map(|(_, s)| s.code).sum::<usize>();
+                (project.root.to_string_lossy().into_owned(), code)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                (String::from("/repo"), 5),
+                (String::from("/repo/api"), 150),
+                (String::from("/repo/web"), 30),
+                (String::from("/repo/web/tools/gen"), 20),
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use clap::ValueEnum;
+
+use crate::{comments::LineStats, lang::Language};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortColumn {
+    Path,
+    Language,
+    Code,
+    Comments,
+    Blanks,
+}
+
+pub struct FileReport {
+    pub path: PathBuf,
+    pub lang: Language,
+    pub stats: LineStats,
+}
+
+impl FileReport {
+    /// Report for a file with only lines of code, for tests
+    #[cfg(test)]
+    pub fn with_code(path: &str, lang: Language, code: usize) -> FileReport {
+        FileReport {
+            path: PathBuf::from(path),
+            lang,
+            stats: LineStats {
+                code,
+                total: code,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Sorts paths and languages alphabetically, and line counts from largest to smallest
+pub fn sort_files(files: &mut [FileReport], column: SortColumn) {
+    files.sort_by(|a, b| {
+        let ordering = match column {
+            SortColumn::Path => a.path.cmp(&b.path),
+            SortColumn::Language => a.lang.to_string().cmp(&b.lang.to_string()),
+            SortColumn::Code => b.stats.code.cmp(&a.stats.code),
+            SortColumn::Comments => b.stats.comments.cmp(&a.stats.comments),
+            SortColumn::Blanks => b.stats.blanks.cmp(&a.stats.blanks),
+        };
+
+        match ordering {
+            Ordering::Equal => a.path.cmp(&b.path),
+            ordering => ordering,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn files() -> Vec<FileReport> {
+        vec![
+            FileReport {
+                path: PathBuf::from("b.rs"),
+                lang: Language::Rust,
+                stats: LineStats {
+                    code: 10,
+                    comments: 1,
+                    blanks: 7,
+                    total: 18,
+                },
+            },
+            FileReport {
+                path: PathBuf::from("a.c"),
+                lang: Language::C,
+                stats: LineStats {
+                    code: 30,
+                    comments: 0,
+                    blanks: 7,
+                    total: 37,
+                },
+            },
+            FileReport {
+                path: PathBuf::from("c.py"),
+                lang: Language::Python,
+                stats: LineStats {
+                    code: 20,
+                    comments: 5,
+                    blanks: 2,
+                    total: 27,
+                },
+            },
+        ]
+    }
+
+    #[test_case(SortColumn::Path, &["a.c", "b.rs", "c.py"])]
+    #[test_case(SortColumn::Language, &["a.c", "c.py", "b.rs"])]
+    #[test_case(SortColumn::Code, &["a.c", "c.py", "b.rs"])]
+    #[test_case(SortColumn::Comments, &["c.py", "b.rs", "a.c"])]
+    #[test_case(SortColumn::Blanks, &["a.c", "b.rs", "c.py"])]
+    fn test_sort_files(column: SortColumn, expected: &[&str]) {
+        let mut files = files();
+        sort_files(&mut files, column);
+
+        let actual = files
+            .iter()
+            .map(|f| f.path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+}
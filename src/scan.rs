@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    },
+};
+
+use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState};
+
+use crate::{
+    comments::{
+        CodeReader, LineStats, ReadError, decode, read_source, read_source_ends, source_ends,
+    },
+    lang::{self, Language, SiblingSources},
+    report::FileReport,
+};
+
+pub struct ScanOptions {
+    /// Lowercase names of languages to leave out
+    pub exclude: Vec<String>,
+    pub debug: bool,
+    /// Keep a report for every counted file
+    pub collect_files: bool,
+    pub gitignore: bool,
+    /// Number of walker threads, 0 picks automatically
+    pub threads: usize,
+}
+
+// Same limit as a shebang line, modelines and shebangs are only looked for near the start and end
+const ENDS_LENGTH: usize = 256;
+
+// Files the path says nothing about are unlikely to be source code when they're larger than this
+const MAX_UNKNOWN_SIZE: u64 = 1024 * 1024;
+
+/// Whether a file of `size` bytes is worth reading to find out its language
+pub fn is_candidate(path: &Path, size: u64) -> bool {
+    lang::is_known_path(path) || size <= MAX_UNKNOWN_SIZE
+}
+
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: ReadError,
+}
+
+#[derive(Default)]
+pub struct Scan {
+    pub languages: HashMap<Language, LineStats>,
+    pub files: Vec<FileReport>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+impl Scan {
+    fn add_file(&mut self, path: &Path, siblings: &SiblingSources, options: &ScanOptions) {
+        // Don't read all of a file the path says nothing about unless its ends name a language
+        let recognized = lang::is_known_path(path)
+            || (std::fs::metadata(path).is_ok_and(|m| is_candidate(path, m.len()))
+                && ends_name_language(path, read_source_ends(path, ENDS_LENGTH), siblings));
+        if !recognized {
+            unrecognized(path, options);
+            return;
+        }
+
+        self.add_contents(path, read_source(path), siblings, options);
+    }
+
+    fn add_contents(
+        &mut self,
+        path: &Path,
+        contents: Result<String, ReadError>,
+        siblings: &SiblingSources,
+        options: &ScanOptions,
+    ) {
+        let lang = match contents {
+            Ok(ref contents) => lang::determine_language(path, contents, siblings),
+            // The path alone might be enough to tell which language failed to be read
+            Err(_) => lang::determine_language(path, "", siblings),
+        };
+        let Some(lang) = lang else {
+            unrecognized(path, options);
+            return;
+        };
+        if options
+            .exclude
+            .iter()
+            .any(|exclude| lang.to_string().to_lowercase() == *exclude)
+        {
+            return;
+        }
+
+        let reader = match contents {
+            Ok(contents) => CodeReader::new(contents, lang),
+            Err(reason) => {
+                if options.debug {
+                    eprintln!("{}: skipped, {}", path.to_string_lossy(), reason)
+                }
+                self.skipped.push(SkippedFile {
+                    path: path.to_path_buf(),
+                    reason,
+                });
+                return;
+            }
+        };
+        let stats = reader.stats();
+
+        *self.languages.entry(lang).or_default() += stats;
+
+        if options.collect_files {
+            self.files.push(FileReport {
+                path: path.to_path_buf(),
+                lang,
+                stats,
+            });
+        }
+
+        if options.debug {
+            eprintln!("{}: {}, {} lines", path.to_string_lossy(), lang, stats.code)
+        }
+    }
+
+    fn merge(&mut self, other: Scan) {
+        for (lang, stats) in other.languages {
+            *self.languages.entry(lang).or_default() += stats;
+        }
+        self.files.extend(other.files);
+        self.skipped.extend(other.skipped);
+    }
+}
+
+/// Whether the start or end of a file, e.g. from `read_source_ends`, names its language in a
+/// modeline or shebang
+fn ends_name_language(
+    path: &Path,
+    ends: Result<String, ReadError>,
+    siblings: &SiblingSources,
+) -> bool {
+    ends.is_ok_and(|ends| lang::determine_language(path, &ends, siblings).is_some())
+}
+
+fn unrecognized(path: &Path, options: &ScanOptions) {
+    if options.debug {
+        eprintln!("{}: unrecognized file", path.to_string_lossy())
+    }
+}
+
+pub fn scan_directory(root: &Path, options: &ScanOptions) -> Scan {
+    let mut builder = WalkBuilder::new(root);
+    builder.threads(options.threads);
+    if options.gitignore {
+        builder.git_ignore(false).ignore(false);
+    };
+
+    // Every file is listed before any is read, so headers can be compared with the rest of their
+    // directory
+    let files = Mutex::new(Vec::new());
+    builder
+        .build_parallel()
+        .visit(&mut WalkVisitorBuilder { files: &files });
+
+    scan_files(&files.into_inner().unwrap(), options)
+}
+
+/// Counts a known list of files, e.g. the files tracked by git
+pub fn scan_files(files: &[PathBuf], options: &ScanOptions) -> Scan {
+    let siblings = SiblingSources::new(files.iter().map(PathBuf::as_path));
+
+    scan_parallel(files, options, |scan, path| {
+        scan.add_file(path, &siblings, options)
+    })
+}
+
+/// Counts files that are already in memory, e.g. blobs read from a git revision
+pub fn scan_blobs(blobs: &[(PathBuf, Vec<u8>)], options: &ScanOptions) -> Scan {
+    let siblings = SiblingSources::new(blobs.iter().map(|(path, _)| path.as_path()));
+
+    scan_parallel(blobs, options, |scan, (path, bytes)| {
+        // Same as the working tree, so both agree on files the path says nothing about
+        if !lang::is_known_path(path)
+            && !ends_name_language(path, source_ends(bytes, ENDS_LENGTH), &siblings)
+        {
+            unrecognized(path, options);
+            return;
+        }
+
+        scan.add_contents(path, decode(bytes), &siblings, options)
+    })
+}
+
+fn scan_parallel<T: Sync>(
+    items: &[T],
+    options: &ScanOptions,
+    add: impl Fn(&mut Scan, &T) + Sync,
+) -> Scan {
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Scan::default());
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(items.len()) {
+            scope.spawn(|| {
+                let mut local = Scan::default();
+                while let Some(item) = items.get(next.fetch_add(1, AtomicOrdering::Relaxed)) {
+                    add(&mut local, item);
+                }
+                results.lock().unwrap().merge(local);
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+struct WalkVisitorBuilder<'a> {
+    files: &'a Mutex<Vec<PathBuf>>,
+}
+
+impl<'s> ParallelVisitorBuilder<'s> for WalkVisitorBuilder<'s> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(WalkVisitor {
+            local: Vec::new(),
+            files: self.files,
+        })
+    }
+}
+
+/// Lists files on a single walker thread, merging into the shared list when the thread is done
+struct WalkVisitor<'a> {
+    local: Vec<PathBuf>,
+    files: &'a Mutex<Vec<PathBuf>>,
+}
+
+impl ParallelVisitor for WalkVisitor<'_> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> WalkState {
+        match entry {
+            Err(e) => eprintln!("Error: {}", e),
+            Ok(entry) => {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    self.local.push(entry.into_path());
+                }
+            }
+        }
+
+        WalkState::Continue
+    }
+}
+
+impl Drop for WalkVisitor<'_> {
+    fn drop(&mut self) {
+        let local = std::mem::take(&mut self.local);
+        self.files.lock().unwrap().extend(local);
+    }
+}
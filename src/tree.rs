@@ -0,0 +1,123 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use crate::{comments::LineStats, lang::Language, report::FileReport};
+
+pub struct DirNode {
+    pub name: String,
+    pub languages: HashMap<Language, LineStats>,
+    pub children: BTreeMap<String, DirNode>,
+}
+
+impl DirNode {
+    fn new(name: String) -> DirNode {
+        DirNode {
+            name,
+            languages: HashMap::new(),
+            children: BTreeMap::new(),
+        }
+    }
+
+    pub fn code(&self) -> usize {
+        self.languages.values().map(|stats| stats.code).sum()
+    }
+
+    /// Languages in the directory and everything under it, sorted from most to least code
+    pub fn languages(&self) -> Vec<(Language, LineStats)> {
+        self.languages
+            .iter()
+            .map(|(lang, stats)| (*lang, *stats))
+            .sorted_by(|a, b| {
+                Ord::cmp(&b.1.code, &a.1.code).then(a.0.to_string().cmp(&b.0.to_string()))
+            })
+            .collect()
+    }
+
+    /// Subdirectories sorted from most to least code
+    pub fn children(&self) -> Vec<&DirNode> {
+        self.children
+            .values()
+            .sorted_by(|a, b| b.code().cmp(&a.code()).then(a.name.cmp(&b.name)))
+            .collect()
+    }
+}
+
+/// Adds up the files under each directory, down to `depth` levels below `root`. Deeper files
+/// are counted in their ancestor at the maximum depth.
+pub fn build_tree(files: &[FileReport], root: &Path, depth: usize) -> DirNode {
+    let mut tree = DirNode::new(String::from("."));
+
+    for file in files {
+        let relative = file.path.strip_prefix(root).unwrap_or(&file.path);
+        let dirs = relative
+            .parent()
+            .into_iter()
+            .flat_map(|parent| parent.iter());
+
+        let mut node = &mut tree;
+        *node.languages.entry(file.lang).or_default() += file.stats;
+        for dir in dirs.take(depth) {
+            let name = dir.to_string_lossy().into_owned();
+            node = node
+                .children
+                .entry(name.clone())
+                .or_insert_with(|| DirNode::new(name));
+            *node.languages.entry(file.lang).or_default() += file.stats;
+        }
+    }
+
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn files() -> Vec<FileReport> {
+        let file = FileReport::with_code;
+
+        vec![
+            file("/repo/build.rs", Language::Rust, 5),
+            file("/repo/src/main.rs", Language::Rust, 100),
+            file("/repo/src/ui/app.ts", Language::TypeScript, 40),
+            file("/repo/src/ui/widgets/button.ts", Language::TypeScript, 10),
+            file("/repo/scripts/release.sh", Language::Shell, 20),
+        ]
+    }
+
+    fn describe(node: &DirNode, prefix: &str, lines: &mut Vec<String>) {
+        let path = format!("{}{}", prefix, node.name);
+        lines.push(format!("{} {}", path, node.code()));
+        for child in node.children() {
+            describe(child, &format!("{}/", path), lines);
+        }
+    }
+
+    #[test_case(0, &[". 175"])]
+    #[test_case(1, &[". 175", "./src 150", "./scripts 20"])]
+    #[test_case(3, &[". 175", "./src 150", "./src/ui 50", "./src/ui/widgets 10", "./scripts 20"])]
+    fn test_build_tree(depth: usize, expected: &[&str]) {
+        let tree = build_tree(&files(), Path::new("/repo"), depth);
+
+        let mut actual = Vec::new();
+        describe(&tree, "", &mut actual);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_dominant_language() {
+        let tree = build_tree(&files(), Path::new("/repo"), 2);
+
+        let src = &tree.children["src"];
+        let ui = &src.children["ui"];
+
+        assert_eq!(src.languages()[0].0, Language::Rust);
+        assert_eq!(ui.languages()[0].0, Language::TypeScript);
+    }
+}